@@ -6,10 +6,12 @@
 //
 // Thales Matheus Mendonça Santos - November 2025
 
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Parser, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Tool to locally synchronize repositories from a GitHub profile.
-/// Modes: own repos (default), starred repos, repos from followers, following, or watching.
+/// Modes: own repos (default), starred repos, repos from followers, following, watching, an organization, all repos accessible to the authenticated user, or gists.
 #[derive(Parser, Debug)]
 #[command(
     author,
@@ -18,12 +20,12 @@
     long_about = None,
     group(
         ArgGroup::new("source")
-            .args(["stars", "following", "followers", "watching"])
+            .args(["stars", "following", "followers", "watching", "org", "mine", "gists"])
             .multiple(false)
     )
 )]
 pub struct Cli {
-    /// The GitHub username
+    /// The GitHub username (or organization name when using --org)
     pub username: String,
 
     /// Sync repositories the user has starred
@@ -42,14 +44,68 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub watching: bool,
 
+    /// Treat the name as a GitHub organization and sync its repositories
+    #[arg(long, default_value_t = false)]
+    pub org: bool,
+
+    /// Organization repository type filter (only used with --org)
+    #[arg(long, value_enum, default_value_t = OrgRepoType::All, requires = "org")]
+    pub org_type: OrgRepoType,
+
+    /// Sync every repository the authenticated user can access, including private ones (requires --token)
+    #[arg(long, default_value_t = false)]
+    pub mine: bool,
+
+    /// Sync the user's gists (including secret gists when authenticated as that user)
+    #[arg(long, default_value_t = false)]
+    pub gists: bool,
+
+    /// Affiliations to include with --mine (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Affiliation::Owner, Affiliation::Collaborator, Affiliation::OrganizationMember],
+        requires = "mine"
+    )]
+    pub affiliation: Vec<Affiliation>,
+
+    /// Visibility filter for --mine
+    #[arg(long, value_enum, default_value_t = Visibility::All, requires = "mine")]
+    pub visibility: Visibility,
+
     /// Include forked repositories in synchronization
     #[arg(long, default_value_t = false)]
     pub include_forks: bool,
 
+    /// Also back up each repository's wiki into a sibling `<repo>.wiki` directory
+    #[arg(long, default_value_t = false)]
+    pub include_wikis: bool,
+
+    /// Export issues, pull requests, comments, labels and milestones as JSON into `<repo>.meta`
+    #[arg(long, default_value_t = false, conflicts_with = "gists")]
+    pub issues: bool,
+
+    /// Back up release notes, metadata and assets into `<repo>.releases/<tag>/`
+    #[arg(long, default_value_t = false, conflicts_with = "gists")]
+    pub releases: bool,
+
     /// GitHub token for authenticated API requests (env: GITHUB_TOKEN)
     #[arg(long, env = "GITHUB_TOKEN")]
     pub token: Option<String>,
 
+    /// GitHub API base URL, e.g. a GitHub Enterprise Server host (env: GITHUB_API_URL)
+    #[arg(long, env = "GITHUB_API_URL", default_value = "https://api.github.com")]
+    pub api_url: String,
+
+    /// PEM file with additional CA certificates to trust for API requests (env: GITHUB_CA_CERT)
+    #[arg(long, env = "GITHUB_CA_CERT")]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Bypass the on-disk ETag cache and always download API listings in full
+    #[arg(long, default_value_t = false)]
+    pub no_cache: bool,
+
     /// Maximum number of concurrent git operations (clone/pull)
     #[arg(short, long, default_value_t = 8)]
     pub concurrency: usize,
@@ -58,7 +114,329 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub exact_mirror: bool,
 
+    /// Check every repository, including those not pushed to since their last successful sync
+    #[arg(long, default_value_t = false)]
+    pub full: bool,
+
+    /// Fetch and filter repositories, then print what would be cloned, updated or pruned without changing anything
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// With --dry-run, also write the plan as JSON to this file
+    #[arg(long, value_name = "PATH", requires = "dry_run")]
+    pub plan_json: Option<PathBuf>,
+
+    /// Refuse to prune more than this share (e.g. 10%) or number (e.g. 25) of existing repositories
+    #[arg(long, value_name = "LIMIT", default_value = "10%", value_parser = parse_prune_limit)]
+    pub max_prune: PruneLimit,
+
+    /// Prune with --exact-mirror even beyond the --max-prune limit
+    #[arg(long, default_value_t = false)]
+    pub yes_prune_all: bool,
+
+    /// How long pruned repositories are kept in `.trash` before being deleted (e.g. 30d, 12h)
+    #[arg(long, value_name = "DURATION", default_value = "30d", value_parser = parse_duration)]
+    pub trash_retention: Duration,
+
+    /// Move a pruned repository back from `.trash`, given as <stamp>/<path> (as printed when pruned)
+    #[arg(long, value_name = "ENTRY", conflicts_with = "dry_run")]
+    pub restore: Option<PathBuf>,
+
     /// Force update existing repositories, discarding local changes and divergent history
     #[arg(long, default_value_t = false)]
     pub force: bool,
+
+    /// Delete local remote-tracking branches and tags that were removed upstream
+    #[arg(long, default_value_t = false)]
+    pub prune: bool,
+
+    /// Fetch Git LFS objects (all refs) for repositories that use LFS (requires git-lfs)
+    #[arg(long, default_value_t = false)]
+    pub lfs: bool,
+
+    /// Initialize and update submodules recursively after every clone and update
+    #[arg(long, default_value_t = false, conflicts_with = "mirror")]
+    pub submodules: bool,
+
+    /// Create shallow clones with only the latest N commits and keep them shallow on update
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "mirror")]
+    pub depth: Option<u32>,
+
+    /// Create partial clones that download blobs (blob:none) or trees and blobs (tree:0) on demand
+    #[arg(long, value_enum, value_name = "FILTER")]
+    pub filter: Option<CloneFilter>,
+
+    /// Transport used to clone and fetch repositories
+    #[arg(long, value_enum, default_value_t = Protocol::Https)]
+    pub protocol: Protocol,
+
+    /// SSH private key used for git over SSH
+    #[arg(long, value_name = "PATH", conflicts_with = "ssh_command")]
+    pub ssh_key: Option<PathBuf>,
+
+    /// SSH command used for git over SSH (overrides GIT_SSH_COMMAND)
+    #[arg(long, value_name = "COMMAND")]
+    pub ssh_command: Option<String>,
+
+    /// Store bare mirror clones (all branches, tags and refs) instead of working copies
+    #[arg(long, default_value_t = false, conflicts_with = "force")]
+    pub mirror: bool,
+}
+
+// Parse a duration such as `30d`, `12h`, `45m`, `90s` or `2w`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => {
+            return Err(format!(
+                "unknown duration unit '{}' (use s, m, h, d or w)",
+                unit
+            ))
+        }
+    };
+    Ok(Duration::from_secs(number.saturating_mul(seconds)))
+}
+
+/// Upper bound on how many existing repositories `--exact-mirror` may prune in one run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PruneLimit {
+    Count(usize),
+    Percent(f64),
+}
+
+impl PruneLimit {
+    /// Whether pruning `stale` of `existing` repositories stays within the limit.
+    pub fn allows(&self, stale: usize, existing: usize) -> bool {
+        match *self {
+            PruneLimit::Count(max) => stale <= max,
+            PruneLimit::Percent(max) => {
+                existing == 0 || stale as f64 * 100.0 <= max * existing as f64
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for PruneLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PruneLimit::Count(max) => write!(f, "{} repositories", max),
+            PruneLimit::Percent(max) => write!(f, "{}%", max),
+        }
+    }
+}
+
+// Parse `--max-prune` as a percentage (`10%`) or a count (`25`).
+fn parse_prune_limit(value: &str) -> Result<PruneLimit, String> {
+    let value = value.trim();
+    match value.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|p| (0.0..=100.0).contains(p))
+            .map(PruneLimit::Percent)
+            .ok_or_else(|| format!("invalid percentage '{}' (expected 0-100%)", value)),
+        None => value.parse().map(PruneLimit::Count).map_err(|_| {
+            format!(
+                "invalid limit '{}' (expected a count or a percentage)",
+                value
+            )
+        }),
+    }
+}
+
+/// Repository type filter accepted by the `/orgs/{org}/repos` endpoint.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrgRepoType {
+    All,
+    Public,
+    Private,
+    Forks,
+    Sources,
+    Member,
+}
+
+impl OrgRepoType {
+    /// Value sent in the `type` query parameter.
+    pub fn as_query(&self) -> &'static str {
+        match self {
+            OrgRepoType::All => "all",
+            OrgRepoType::Public => "public",
+            OrgRepoType::Private => "private",
+            OrgRepoType::Forks => "forks",
+            OrgRepoType::Sources => "sources",
+            OrgRepoType::Member => "member",
+        }
+    }
+}
+
+/// Relationship to a repository accepted by the `/user/repos` `affiliation` parameter.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Affiliation {
+    Owner,
+    Collaborator,
+    OrganizationMember,
+}
+
+impl Affiliation {
+    /// Value sent in the `affiliation` query parameter.
+    pub fn as_query(&self) -> &'static str {
+        match self {
+            Affiliation::Owner => "owner",
+            Affiliation::Collaborator => "collaborator",
+            Affiliation::OrganizationMember => "organization_member",
+        }
+    }
+}
+
+/// Visibility filter accepted by the `/user/repos` endpoint.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    All,
+    Public,
+    Private,
+}
+
+impl Visibility {
+    /// Value sent in the `visibility` query parameter.
+    pub fn as_query(&self) -> &'static str {
+        match self {
+            Visibility::All => "all",
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+/// Transport used for git remotes.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// HTTPS remotes (`clone_url`)
+    Https,
+    /// SSH remotes (`ssh_url`)
+    Ssh,
+}
+
+/// Partial clone filter passed to `git clone --filter`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloneFilter {
+    #[value(name = "blob:none")]
+    BlobNone,
+    #[value(name = "tree:0")]
+    TreeZero,
+}
+
+impl CloneFilter {
+    /// Filter spec given to git.
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            CloneFilter::BlobNone => "blob:none",
+            CloneFilter::TreeZero => "tree:0",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_cases() {
+        let cases = [
+            ("90s", Some(90)),
+            ("45m", Some(45 * 60)),
+            ("12h", Some(12 * 3600)),
+            ("30d", Some(30 * 86_400)),
+            ("2w", Some(14 * 86_400)),
+            // A bare number counts days.
+            ("7", Some(7 * 86_400)),
+            (" 0d ", Some(0)),
+            ("", None),
+            ("d", None),
+            ("-1d", None),
+            ("1.5d", None),
+            ("3y", None),
+            ("10 d", None),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(
+                parse_duration(value).ok(),
+                expected.map(Duration::from_secs),
+                "value: {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn parse_prune_limit_cases() {
+        let cases = [
+            ("10%", Some(PruneLimit::Percent(10.0))),
+            (" 2.5 % ", Some(PruneLimit::Percent(2.5))),
+            ("0%", Some(PruneLimit::Percent(0.0))),
+            ("100%", Some(PruneLimit::Percent(100.0))),
+            ("25", Some(PruneLimit::Count(25))),
+            ("0", Some(PruneLimit::Count(0))),
+            ("101%", None),
+            ("-5%", None),
+            ("%", None),
+            ("-1", None),
+            ("ten", None),
+            ("", None),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(
+                parse_prune_limit(value).ok(),
+                expected,
+                "value: {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn prune_limit_allows_cases() {
+        // (limit, stale, existing, allowed)
+        let cases = [
+            (PruneLimit::Count(0), 0, 10, true),
+            (PruneLimit::Count(0), 1, 10, false),
+            (PruneLimit::Count(3), 3, 4, true),
+            (PruneLimit::Count(3), 4, 100, false),
+            (PruneLimit::Percent(10.0), 10, 100, true),
+            (PruneLimit::Percent(10.0), 11, 100, false),
+            (PruneLimit::Percent(10.0), 0, 0, true),
+            // Small backups cannot prune even one repository under the default 10%.
+            (PruneLimit::Percent(10.0), 1, 9, false),
+            (PruneLimit::Percent(10.0), 1, 10, true),
+            (PruneLimit::Percent(10.0), 3, 6, false),
+            (PruneLimit::Percent(0.0), 0, 1000, true),
+            (PruneLimit::Percent(0.0), 1, 1000, false),
+            (PruneLimit::Percent(100.0), 50, 50, true),
+        ];
+
+        for (limit, stale, existing, allowed) in cases {
+            assert_eq!(
+                limit.allows(stale, existing),
+                allowed,
+                "{} pruning {} of {}",
+                limit,
+                stale,
+                existing
+            );
+        }
+    }
 }
@@ -0,0 +1,64 @@
+//
+// cache.rs
+// GitHub Replicant (Rust)
+//
+// Persists GitHub API listing pages on disk together with their ETag/Last-Modified validators, so repeated runs can issue conditional requests and reuse the cached JSON on 304 Not Modified.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use crate::files;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// A cached API page: validators to send back, plus what we need to replay a 304.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Raw `Link` header so pagination still works when the page is served from cache.
+    pub link: Option<String>,
+    pub body: String,
+}
+
+/// On-disk cache of API pages, one JSON file per URL.
+pub struct ApiCache {
+    dir: PathBuf,
+}
+
+impl ApiCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Load the cached page for a URL. Missing or unreadable entries are treated as a cache miss.
+    pub async fn load(&self, url: &str) -> Option<CachedPage> {
+        let contents = tokio::fs::read(self.entry_path(url)).await.ok()?;
+        let page: CachedPage = serde_json::from_slice(&contents).ok()?;
+        // Guard against (unlikely) hash collisions between URLs.
+        (page.url == url).then_some(page)
+    }
+
+    /// Store a page for its URL, replacing any previous entry.
+    pub async fn store(&self, page: &CachedPage) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create cache directory {:?}", self.dir))?;
+
+        let path = self.entry_path(&page.url);
+        let contents = serde_json::to_vec(page).context("Failed to serialize cache entry")?;
+
+        files::write_atomic(&path, contents).await
+    }
+
+    // Cache file name derived from the URL. The hash only needs to be stable between runs of the
+    // same binary; a different hash after an upgrade just means a one-time cache miss.
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
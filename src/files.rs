@@ -0,0 +1,31 @@
+//
+// files.rs
+// GitHub Replicant (Rust)
+//
+// Filesystem helpers shared by the sync steps: naming the directories kept next to each clone (wiki, metadata, releases) and replacing files atomically.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Location of a directory kept next to a repository's clone: `<repo><suffix>`.
+pub fn sibling_path(repo_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = repo_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    repo_path.with_file_name(name)
+}
+
+/// Replace `path` with `contents`. Writes to a temporary file first so an interrupted run never
+/// leaves a truncated file behind.
+pub async fn write_atomic(path: &Path, contents: Vec<u8>) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    tokio::fs::write(&tmp_path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+    tokio::fs::rename(&tmp_path, path)
+        .await
+        .with_context(|| format!("Failed to write {:?}", path))
+}
@@ -2,16 +2,106 @@
 // git.rs
 // GitHub Replicant (Rust)
 //
-// Wraps git operations for repositories: ensures destination paths exist, then clones new repos or pulls updates on existing ones using async process execution and error surfacing.
+// Wraps git operations for repositories: ensures destination paths exist, then clones new repos or pulls updates on existing ones (or maintains bare mirrors) using async process execution and error surfacing.
 //
 // Thales Matheus Mendonça Santos - November 2025
 
+use crate::files;
 use crate::github::Repo;
+use crate::manifest;
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::Path;
 use std::process::Stdio;
+use std::sync::OnceLock;
 use tokio::process::Command;
+use walkdir::WalkDir;
+
+/// Namespace of refs kept by this tool; never touched by fetches and excluded from ref counts.
+const REPLICANT_REF_PREFIX: &str = "refs/replicant/";
+
+/// Namespace of refs holding local commits that a forced update would have discarded.
+const BACKUP_REF_PREFIX: &str = "refs/replicant/backup";
+
+/// Namespace of refs holding previous tips of refs that were force-pushed upstream.
+const OVERWRITTEN_REF_PREFIX: &str = "refs/replicant/overwritten";
+
+/// Negative refspec keeping `--prune` on a mirror (`+refs/*:refs/*`) from deleting our refs.
+const MIRROR_PROTECT_REFSPEC: &str = "^refs/replicant/*";
+
+/// Pull request refs mirrored from GitHub. `refs/pull/<n>/merge` is recomputed whenever the base
+/// branch moves, so these are neither counted nor checked for force-pushes.
+const PULL_REF_PREFIX: &str = "refs/pull/";
+
+/// Environment variable carrying the token to the credential helper configured by
+/// `token_environment`.
+const TOKEN_ENV: &str = "REPLICANT_GIT_TOKEN";
+
+/// Extra environment variables for every git process (e.g. `GIT_SSH_COMMAND`).
+static GIT_ENVIRONMENT: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Sets environment variables passed to every git command. Only the first call takes effect;
+/// call it once before syncing.
+pub fn set_git_environment(vars: Vec<(String, String)>) {
+    let _ = GIT_ENVIRONMENT.set(vars);
+}
+
+/// Environment that makes git authenticate HTTPS requests to `hosts` with a GitHub token.
+/// The token only travels through the environment: the credential helper is configured with
+/// `GIT_CONFIG_*` variables (nothing is written to `.git/config`) and reads it from `TOKEN_ENV`,
+/// so it never appears in process arguments. Other credential helpers are reset for these hosts
+/// so the token is not stored in a keychain either.
+pub fn token_environment(token: &str, hosts: &[String]) -> Vec<(String, String)> {
+    let helper = format!(
+        "!f() {{ test \"$1\" = get && echo username=x-access-token && echo \"password=${}\"; }}; f",
+        TOKEN_ENV
+    );
+    let mut entries = Vec::new();
+    for host in hosts {
+        let key = format!("credential.{}.helper", host);
+        entries.push((key.clone(), String::new()));
+        entries.push((key, helper.clone()));
+    }
+
+    let mut vars = vec![
+        (TOKEN_ENV.to_string(), token.to_string()),
+        ("GIT_CONFIG_COUNT".to_string(), entries.len().to_string()),
+    ];
+    for (index, (key, value)) in entries.into_iter().enumerate() {
+        vars.push((format!("GIT_CONFIG_KEY_{}", index), key));
+        vars.push((format!("GIT_CONFIG_VALUE_{}", index), value));
+    }
+    vars
+}
+
+// Mask the token in git output before it is surfaced in errors.
+fn redact(output: &str) -> String {
+    let token = GIT_ENVIRONMENT
+        .get()
+        .and_then(|vars| vars.iter().find(|(key, _)| key == TOKEN_ENV))
+        .map(|(_, value)| value.as_str());
+    match token {
+        Some(token) if !token.is_empty() => output.replace(token, "***"),
+        _ => output.to_string(),
+    }
+}
+
+// A `git` command in the given directory, with the configured environment applied.
+fn git_command(cwd: Option<&Path>) -> Command {
+    // Use tokio::process::Command for non-blocking execution
+    let mut command = Command::new("git");
+    if let Some(path) = cwd {
+        command.current_dir(path);
+    }
+    // Fail instead of asking for credentials: runs are unattended, and GitHub answers with an
+    // authentication request for repositories (and wikis) that do not exist.
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(vars) = GIT_ENVIRONMENT.get() {
+        command.envs(vars.iter().map(|(key, value)| (key, value)));
+    }
+    command
+}
 
 /// Executes a git command asynchronously and captures the output.
 async fn run_git_command<I, S>(args: I, cwd: Option<&Path>) -> Result<()>
@@ -19,11 +109,7 @@ async fn run_git_command<I, S>(args: I, cwd: Option<&Path>) -> Result<()>
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    // Use tokio::process::Command for non-blocking execution
-    let mut command = Command::new("git");
-    if let Some(path) = cwd {
-        command.current_dir(path);
-    }
+    let mut command = git_command(cwd);
     command.args(args);
 
     // Capture stdout and stderr to avoid mixing output in the terminal
@@ -40,7 +126,7 @@ async fn run_git_command<I, S>(args: I, cwd: Option<&Path>) -> Result<()>
     } else {
         // If failed, return stderr for diagnosis
         let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(anyhow::anyhow!("Git command failed: {}", stderr))
+        Err(anyhow::anyhow!("Git command failed: {}", redact(&stderr)))
     }
 }
 
@@ -50,10 +136,7 @@ async fn run_git_command_output<I, S>(args: I, cwd: Option<&Path>) -> Result<Str
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    let mut command = Command::new("git");
-    if let Some(path) = cwd {
-        command.current_dir(path);
-    }
+    let mut command = git_command(cwd);
     command.args(args);
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
@@ -67,12 +150,95 @@ async fn run_git_command_output<I, S>(args: I, cwd: Option<&Path>) -> Result<Str
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(anyhow::anyhow!("Git command failed: {}", stderr))
+        Err(anyhow::anyhow!("Git command failed: {}", redact(&stderr)))
     }
 }
 
-/// Clones the repository if it doesn't exist, or runs 'git pull' if it does.
-pub async fn sync_repository(repo: Repo, repo_path: &Path, force_reset: bool) -> Result<()> {
+/// How repositories are cloned and updated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncOptions {
+    /// Discard local changes and divergent history when updating working copies.
+    pub force_reset: bool,
+    /// Keep bare `--mirror` clones (every ref) instead of working copies.
+    pub mirror: bool,
+    /// Delete remote-tracking branches and tags that no longer exist upstream.
+    pub prune: bool,
+    /// Fetch Git LFS objects for repositories that use LFS.
+    pub lfs: bool,
+    /// Initialize and update submodules recursively (working copies only).
+    pub submodules: bool,
+    /// Create shallow clones with this many commits and keep them shallow on update.
+    pub depth: Option<u32>,
+    /// Partial clone filter such as `blob:none` or `tree:0`.
+    pub filter: Option<&'static str>,
+    /// Clone and fetch over SSH (`ssh_url`) instead of HTTPS (`clone_url`).
+    pub ssh: bool,
+}
+
+/// What a single repository sync changed, for the run summary.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    /// Ref changes observed while fetching an existing repository (empty for fresh clones).
+    pub refs: RefChanges,
+    /// Whether the repository's wiki was cloned or updated alongside it.
+    pub wiki_synced: bool,
+    /// Issues and pull requests exported (new or updated) for this repository.
+    pub issues_exported: usize,
+    /// Release assets downloaded for this repository (already-present assets are not counted).
+    pub release_assets_downloaded: usize,
+    /// Bytes of Git LFS objects downloaded for this repository.
+    pub lfs_bytes: u64,
+    /// Non-fatal problems (e.g. a failed wiki sync) that did not fail the repository itself.
+    pub warnings: Vec<String>,
+    /// Commit at HEAD after the sync (`None` for empty repositories or skipped clones).
+    pub head: Option<String>,
+    /// Git operations were skipped because nothing was pushed since the last sync.
+    pub unchanged: bool,
+    /// Local state kept aside before a forced reset (backup refs, stashes).
+    pub preserved: Vec<String>,
+    /// Refs holding previous tips of branches and tags that were force-pushed upstream.
+    pub overwritten: Vec<String>,
+}
+
+/// Counts of refs added, moved or removed by a fetch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RefChanges {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl RefChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.deleted == 0
+    }
+
+    // Compare two ref snapshots (refname -> object id).
+    fn between(before: &HashMap<String, String>, after: &HashMap<String, String>) -> Self {
+        let mut changes = RefChanges::default();
+        for (name, oid) in after {
+            match before.get(name) {
+                None => changes.added += 1,
+                Some(old) if old != oid => changes.updated += 1,
+                Some(_) => {}
+            }
+        }
+        changes.deleted = before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .count();
+        changes
+    }
+}
+
+/// Clones the repository if it doesn't exist, or fetches every branch and tag and runs
+/// 'git pull' if it does. In mirror mode, keeps a bare mirror updated with
+/// 'git remote update --prune' instead. Afterwards fetches Git LFS objects when requested.
+pub async fn sync_repository(
+    repo: Repo,
+    repo_path: &Path,
+    options: SyncOptions,
+) -> Result<SyncReport> {
     // Ensure the parent directories exist before cloning/pulling.
     if let Some(parent) = repo_path.parent() {
         tokio::fs::create_dir_all(parent)
@@ -80,54 +246,630 @@ pub async fn sync_repository(repo: Repo, repo_path: &Path, force_reset: bool) ->
             .with_context(|| format!("Failed to ensure parent directory for {:?}", repo_path))?;
     }
 
+    // Use the remote for the selected protocol, and repoint existing clones that were made
+    // with the other one.
+    let repo = Repo {
+        clone_url: remote_url(&repo, options.ssh).to_string(),
+        ..repo
+    };
+    if is_bare_repository(repo_path) || repo_path.join(".git").exists() {
+        update_origin_url(repo_path, &repo.clone_url).await?;
+    }
+
+    let mut report = if options.mirror {
+        sync_mirror(&repo, repo_path, options.filter).await?
+    } else {
+        sync_working_copy(&repo, repo_path, options).await?
+    };
+
+    // Submodules are initialized and updated one by one so a broken submodule is reported
+    // without failing its parent repository.
+    if options.submodules && !options.mirror && repo_path.exists() {
+        report.warnings.extend(update_submodules(repo_path).await);
+    }
+
+    // A clone skipped for DMCA leaves no repository behind.
+    if options.lfs && repo_path.exists() {
+        // LFS problems (including a missing git-lfs binary) should not fail the code backup.
+        match fetch_lfs_objects(repo_path, options.mirror).await {
+            Ok(bytes) => report.lfs_bytes = bytes,
+            Err(e) => report.warnings.push(format!("lfs: {}", e)),
+        }
+    }
+
+    if repo_path.exists() {
+        report.head = run_git_command_output(["rev-parse", "--verify", "HEAD"], Some(repo_path))
+            .await
+            .ok();
+    }
+
+    Ok(report)
+}
+
+// Clone or update a working copy.
+async fn sync_working_copy(
+    repo: &Repo,
+    repo_path: &Path,
+    options: SyncOptions,
+) -> Result<SyncReport> {
+    // Never treat an existing bare mirror as an incomplete clone to be wiped.
+    if is_bare_repository(repo_path) {
+        return Err(anyhow::anyhow!(
+            "{:?} is a bare mirror; run with --mirror or remove it to switch to a working copy",
+            repo_path
+        ));
+    }
+
+    let mut report = SyncReport::default();
+    let clone_args = clone_args(options);
+    let clone_args: Vec<&str> = clone_args.iter().map(String::as_str).collect();
+
     // Check if directory exists AND contains a .git folder (indicating a valid repo)
     if repo_path.exists() && repo_path.join(".git").exists() {
+        // Shallow clones keep a truncated history: fetch with the same depth and reset to the
+        // upstream tip, since merging into a shallow history fails or deepens it.
+        let shallow = is_shallow_repository(repo_path).await;
+
+        // Bring in every remote branch and tag, not just the checked-out branch.
+        // Forced updates always prune so the reset target reflects the remote exactly.
+        let depth = if shallow { options.depth } else { None };
+        let tip_before = head_at_upstream(repo_path).await;
+        (report.refs, report.overwritten) =
+            fetch_all_refs(repo_path, options.prune || options.force_reset, depth).await?;
+
         // Repository exists: Update (git pull or forced reset)
-        if force_reset {
-            force_update(repo_path).await
+        // Failures keep naming the force-pushed refs, whose old tips were already kept.
+        let overwritten = report.overwritten.clone();
+        let kept = |err| with_overwritten(err, &overwritten);
+        if options.force_reset || shallow {
+            report.preserved = force_update(repo_path).await.map_err(kept)?;
+        } else if upstream_rewritten(repo_path, tip_before.as_deref()).await {
+            // The branch was force-pushed and we had nothing of our own on top: follow the
+            // rewrite (the old tip is kept under refs/replicant/overwritten). `--keep` refuses
+            // rather than discarding uncommitted changes.
+            run_git_command(["reset", "--keep", "@{u}"], Some(repo_path))
+                .await
+                .map_err(kept)?;
         } else {
             match run_git_command(["pull"], Some(repo_path)).await {
-                Ok(()) => Ok(()),
+                Ok(()) => {}
                 Err(err) if is_default_branch_error(&err) => {
                     println!(
                         "ℹ️ Default branch changed for {}. Re-cloning to match remote.",
                         repo.full_name
                     );
-                    if let Err(remove_err) = tokio::fs::remove_dir_all(repo_path).await {
-                        return Err(err.context(format!(
-                            "Failed to remove repository before re-clone: {:?}",
-                            remove_err
-                        )));
-                    }
-                    clone_repository(&repo, repo_path).await
+                    reclone_working_copy(repo, repo_path, &clone_args)
+                        .await
+                        .map_err(kept)?;
                 }
-                Err(err) => Err(err),
+                Err(err) => return Err(kept(err)),
             }
         }
     } else {
-        clone_repository(&repo, repo_path).await
+        clone_repository(repo, repo_path, &clone_args).await?;
     }
+
+    Ok(report)
 }
 
-// Forcefully update a repository by fetching all remotes and resetting to the upstream branch.
-async fn force_update(repo_path: &Path) -> Result<()> {
-    // Fetch latest changes and prune removed branches.
-    run_git_command(["fetch", "--all", "--prune"], Some(repo_path)).await?;
+// Replace a working copy with a fresh clone, carrying over the refs kept under `refs/replicant/`
+// (saved local commits and force-pushed tips). The old clone is only removed once the new one is
+// complete.
+async fn reclone_working_copy(repo: &Repo, repo_path: &Path, clone_args: &[&str]) -> Result<()> {
+    let fresh = files::sibling_path(repo_path, ".reclone");
+    clone_repository(repo, &fresh, clone_args).await?;
+    if !fresh.exists() {
+        // Skipped (DMCA takedown); keep what we have.
+        return Ok(());
+    }
+
+    // The fetch runs inside the new clone, so the old one must be named by an absolute path.
+    let source = tokio::fs::canonicalize(repo_path)
+        .await
+        .with_context(|| format!("Failed to resolve {:?}", repo_path))?;
+    let refspec = format!("+{}*:{}*", REPLICANT_REF_PREFIX, REPLICANT_REF_PREFIX);
+    let fetch = run_git_command(
+        [
+            OsStr::new("fetch"),
+            OsStr::new("--no-tags"),
+            source.as_os_str(),
+            OsStr::new(&refspec),
+        ],
+        Some(&fresh),
+    )
+    .await;
+    if let Err(err) = fetch {
+        tokio::fs::remove_dir_all(&fresh).await.ok();
+        return Err(anyhow::anyhow!(
+            "Failed to carry refs/replicant over to the new clone: {}",
+            err
+        ));
+    }
 
+    tokio::fs::remove_dir_all(repo_path)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to remove repository {:?} before re-clone",
+                repo_path
+            )
+        })?;
+    tokio::fs::rename(&fresh, repo_path)
+        .await
+        .with_context(|| format!("Failed to move {:?} to {:?}", fresh, repo_path))
+}
+
+// Name the refs kept for force-pushed tips in an update error, so a failed sync still reports them.
+fn with_overwritten(err: anyhow::Error, overwritten: &[String]) -> anyhow::Error {
+    if overwritten.is_empty() {
+        return err;
+    }
+    anyhow::anyhow!(
+        "{} (upstream was force-pushed; previous tips kept at {})",
+        err.to_string().trim_end(),
+        overwritten.join(", ")
+    )
+}
+
+// The checked-out commit, if it is exactly the upstream tip (no local commits on top).
+async fn head_at_upstream(repo_path: &Path) -> Option<String> {
+    let output = run_git_command_output(["rev-parse", "HEAD", "@{u}"], Some(repo_path))
+        .await
+        .ok()?;
+    let mut lines = output.lines();
+    let (head, upstream) = (lines.next()?, lines.next()?);
+    (head == upstream).then(|| head.to_string())
+}
+
+// Whether the upstream moved from `old_tip` to a commit that does not descend from it.
+async fn upstream_rewritten(repo_path: &Path, old_tip: Option<&str>) -> bool {
+    let Some(old_tip) = old_tip else {
+        return false;
+    };
+    let Ok(new_tip) = run_git_command_output(["rev-parse", "@{u}"], Some(repo_path)).await else {
+        return false;
+    };
+    new_tip != old_tip
+        && run_git_command(
+            ["merge-base", "--is-ancestor", old_tip, new_tip.as_str()],
+            Some(repo_path),
+        )
+        .await
+        .is_err()
+}
+
+// Initialize and update every submodule recursively, returning one warning per failed step.
+async fn update_submodules(repo_path: &Path) -> Vec<String> {
+    if !repo_path.join(".gitmodules").exists() {
+        return Vec::new();
+    }
+
+    // Pick up submodule URL changes made upstream in .gitmodules. A broken entry there should not
+    // stop the other submodules from updating.
+    let mut warnings = Vec::new();
+    if let Err(e) = run_git_command(["submodule", "sync", "--recursive"], Some(repo_path)).await {
+        warnings.push(format!("submodule sync: {}", e));
+    }
+
+    let entries = run_git_command_output(
+        [
+            "config",
+            "--file",
+            ".gitmodules",
+            "--get-regexp",
+            r"^submodule\..*\.path$",
+        ],
+        Some(repo_path),
+    )
+    .await
+    .unwrap_or_default();
+
+    for path in entries
+        .lines()
+        .filter_map(|line| line.split_once(' ').map(|(_, p)| p))
+    {
+        if let Err(e) = run_git_command(
+            ["submodule", "update", "--init", "--recursive", "--", path],
+            Some(repo_path),
+        )
+        .await
+        {
+            warnings.push(format!("submodule {}: {}", path, e));
+        }
+    }
+
+    warnings
+}
+
+// Fetch every Git LFS object (all refs) for repositories whose .gitattributes use the lfs filter.
+// Returns the number of bytes added to the local LFS object store.
+async fn fetch_lfs_objects(repo_path: &Path, bare: bool) -> Result<u64> {
+    if !uses_lfs(repo_path).await {
+        return Ok(0);
+    }
+
+    if run_git_command(["lfs", "version"], Some(repo_path))
+        .await
+        .is_err()
+    {
+        return Err(anyhow::anyhow!(
+            "repository uses Git LFS but git-lfs is not installed; LFS objects were not fetched"
+        ));
+    }
+
+    let objects_dir = if bare {
+        repo_path.join("lfs").join("objects")
+    } else {
+        repo_path.join(".git").join("lfs").join("objects")
+    };
+    let before = directory_size(&objects_dir).await;
+
+    run_git_command(["lfs", "fetch", "--all"], Some(repo_path)).await?;
+    // Replace pointer files in the work tree with the real content.
+    if !bare {
+        run_git_command(["lfs", "checkout"], Some(repo_path)).await?;
+    }
+
+    let after = directory_size(&objects_dir).await;
+    Ok(after.saturating_sub(before))
+}
+
+// Detect LFS usage from any .gitattributes file at HEAD that routes paths through `filter=lfs`.
+async fn uses_lfs(repo_path: &Path) -> bool {
+    run_git_command_output(
+        [
+            "grep",
+            "-l",
+            "-e",
+            "filter=lfs",
+            "HEAD",
+            "--",
+            ":(glob)**/.gitattributes",
+        ],
+        Some(repo_path),
+    )
+    .await
+    .map(|files| !files.is_empty())
+    .unwrap_or(false)
+}
+
+// Total size in bytes of the files below a directory (0 if it does not exist).
+async fn directory_size(dir: &Path) -> u64 {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum()
+    })
+    .await
+    .unwrap_or(0)
+}
+
+// Create or refresh a bare mirror clone. Mirrors track every ref and have no work tree,
+// so there are no local changes or divergent branches to reconcile.
+async fn sync_mirror(
+    repo: &Repo,
+    repo_path: &Path,
+    filter: Option<&'static str>,
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+
+    if is_bare_repository(repo_path) {
+        protect_replicant_refs(repo_path).await?;
+        let before = snapshot_refs(repo_path, &["refs"]).await?;
+        // Defer automatic gc until overwritten commits are referenced again.
+        run_git_command(
+            ["-c", "gc.auto=0", "remote", "update", "--prune"],
+            Some(repo_path),
+        )
+        .await?;
+        let after = snapshot_refs(repo_path, &["refs"]).await?;
+        report.refs = RefChanges::between(&before, &after);
+        report.overwritten = keep_overwritten_tips(repo_path, &before, &after).await?;
+        run_git_command(["gc", "--auto", "--quiet"], Some(repo_path)).await?;
+        return Ok(report);
+    }
+
+    // Never wipe a working copy just because the storage mode changed.
+    if repo_path.join(".git").exists() {
+        return Err(anyhow::anyhow!(
+            "{:?} is a working copy; run without --mirror or remove it to switch to a mirror",
+            repo_path
+        ));
+    }
+
+    let mut args = vec!["--mirror".to_string()];
+    if let Some(filter) = filter {
+        args.push(format!("--filter={}", filter));
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    clone_repository(repo, repo_path, &args).await?;
+    if repo_path.exists() {
+        protect_replicant_refs(repo_path).await?;
+    }
+    Ok(report)
+}
+
+/// Suffix of a repository's wiki clone, kept in a sibling `<repo>.wiki` directory.
+pub const WIKI_SUFFIX: &str = ".wiki";
+
+/// Clones or updates the wiki (`<repo>.wiki.git`) next to the repository.
+/// Returns Ok(false) when the wiki has never been initialized on GitHub.
+pub async fn sync_wiki(repo: &Repo, repo_path: &Path, options: SyncOptions) -> Result<bool> {
+    let wiki_url = |url: &str| format!("{}.wiki.git", url.strip_suffix(".git").unwrap_or(url));
+    let wiki = Repo {
+        name: format!("{}.wiki", repo.name),
+        full_name: format!("{}.wiki", repo.full_name),
+        clone_url: wiki_url(&repo.clone_url),
+        ssh_url: wiki_url(&repo.ssh_url),
+        ..repo.clone()
+    };
+
+    match sync_repository(wiki, &files::sibling_path(repo_path, WIKI_SUFFIX), options).await {
+        Ok(_) => Ok(true),
+        // GitHub reports wikis without any pages as missing repositories.
+        Err(err) if is_missing_repository_error(&err) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// URL to clone from with the selected protocol.
+fn remote_url(repo: &Repo, ssh: bool) -> &str {
+    if ssh && !repo.ssh_url.is_empty() {
+        &repo.ssh_url
+    } else {
+        &repo.clone_url
+    }
+}
+
+/// Whether an existing clone's `origin` already points at the URL `sync_repository` would use.
+/// A repository that was renamed or switched protocol needs a sync even if nothing was pushed.
+pub async fn origin_is_current(repo: &Repo, repo_path: &Path, ssh: bool) -> bool {
+    run_git_command_output(["config", "--get", "remote.origin.url"], Some(repo_path))
+        .await
+        .is_ok_and(|url| url == remote_url(repo, ssh))
+}
+
+// Point `origin` at the expected URL. Reads the configured value rather than `git remote get-url`,
+// which would apply `url.<base>.insteadOf` rewrites and never match.
+async fn update_origin_url(repo_path: &Path, url: &str) -> Result<()> {
+    let current = run_git_command_output(["config", "--get", "remote.origin.url"], Some(repo_path))
+        .await
+        .unwrap_or_default();
+    if current != url {
+        run_git_command(["remote", "set-url", "origin", url], Some(repo_path)).await?;
+    }
+    Ok(())
+}
+
+// Extra `git clone` flags for shallow (`--depth`) and partial (`--filter`) clones.
+fn clone_args(options: SyncOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(depth) = options.depth {
+        // --depth implies --single-branch; keep every branch like a regular clone.
+        args.push(format!("--depth={}", depth));
+        args.push("--no-single-branch".to_string());
+    }
+    if let Some(filter) = options.filter {
+        args.push(format!("--filter={}", filter));
+    }
+    args
+}
+
+// Detect a shallow clone (created with --depth).
+async fn is_shallow_repository(repo_path: &Path) -> bool {
+    run_git_command_output(["rev-parse", "--is-shallow-repository"], Some(repo_path))
+        .await
+        .map(|out| out == "true")
+        .unwrap_or(false)
+}
+
+// Fetch all remotes including every tag, reporting how remote-tracking branches and tags moved
+// and the refs keeping previous tips of force-pushed ones. `depth` keeps shallow clones shallow.
+async fn fetch_all_refs(
+    repo_path: &Path,
+    prune: bool,
+    depth: Option<u32>,
+) -> Result<(RefChanges, Vec<String>)> {
+    let patterns = ["refs/remotes", "refs/tags"];
+    let before = snapshot_refs(repo_path, &patterns).await?;
+
+    // Defer automatic gc until overwritten commits are referenced again.
+    let mut args = vec![
+        "-c".to_string(),
+        "gc.auto=0".to_string(),
+        "fetch".to_string(),
+        "--all".to_string(),
+        "--tags".to_string(),
+        // Accept tags that moved upstream instead of failing the fetch; the old tip is kept
+        // by `keep_overwritten_tips` below.
+        "--force".to_string(),
+    ];
+    if prune {
+        args.push("--prune".to_string());
+    }
+    if let Some(depth) = depth {
+        args.push(format!("--depth={}", depth));
+    }
+    run_git_command(args, Some(repo_path)).await?;
+
+    let after = snapshot_refs(repo_path, &patterns).await?;
+    // A shallow history cannot tell a rewrite from a fast-forward past the shallow boundary.
+    let overwritten = if is_shallow_repository(repo_path).await {
+        Vec::new()
+    } else {
+        keep_overwritten_tips(repo_path, &before, &after).await?
+    };
+    run_git_command(["gc", "--auto", "--quiet"], Some(repo_path)).await?;
+
+    Ok((RefChanges::between(&before, &after), overwritten))
+}
+
+// Keep the previous tip of every ref that moved to a commit not descending from it (a
+// force-push) under `refs/replicant/overwritten/<stamp>/`, so the rewritten history stays
+// reachable. Returns the refs created.
+async fn keep_overwritten_tips(
+    repo_path: &Path,
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let stamp = manifest::utc_stamp();
+    let mut kept = Vec::new();
+
+    for (name, new) in after {
+        let Some(old) = before.get(name).filter(|old| *old != new) else {
+            continue;
+        };
+        let fast_forward = run_git_command(
+            ["merge-base", "--is-ancestor", old.as_str(), new.as_str()],
+            Some(repo_path),
+        )
+        .await
+        .is_ok();
+        if fast_forward {
+            continue;
+        }
+
+        let short = name.strip_prefix("refs/").unwrap_or(name);
+        let backup_ref = format!("{}/{}/{}", OVERWRITTEN_REF_PREFIX, stamp, short);
+        run_git_command(
+            ["update-ref", backup_ref.as_str(), old.as_str()],
+            Some(repo_path),
+        )
+        .await?;
+        kept.push(backup_ref);
+    }
+
+    kept.sort();
+    Ok(kept)
+}
+
+// Make sure pruning fetches of a mirror leave `refs/replicant/` alone.
+async fn protect_replicant_refs(repo_path: &Path) -> Result<()> {
+    let refspecs = run_git_command_output(
+        ["config", "--get-all", "remote.origin.fetch"],
+        Some(repo_path),
+    )
+    .await
+    .unwrap_or_default();
+    if !refspecs.lines().any(|line| line == MIRROR_PROTECT_REFSPEC) {
+        run_git_command(
+            [
+                "config",
+                "--add",
+                "remote.origin.fetch",
+                MIRROR_PROTECT_REFSPEC,
+            ],
+            Some(repo_path),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Map every ref under the given prefixes to the object it points at, leaving out symbolic refs
+// (such as `origin/HEAD`), the refs kept by this tool and pull request refs.
+async fn snapshot_refs(repo_path: &Path, patterns: &[&str]) -> Result<HashMap<String, String>> {
+    let mut args = vec![
+        "for-each-ref",
+        "--format=%(objectname) %(refname) %(symref)",
+    ];
+    args.extend_from_slice(patterns);
+    let output = run_git_command_output(args, Some(repo_path)).await?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let oid = fields.next()?;
+            let name = fields.next()?;
+            let symref = fields.next().unwrap_or("");
+            (symref.is_empty()
+                && !name.starts_with(REPLICANT_REF_PREFIX)
+                && !name.starts_with(PULL_REF_PREFIX))
+            .then(|| (name.to_string(), oid.to_string()))
+        })
+        .collect())
+}
+
+// Detect a bare repository (as created by `git clone --mirror`).
+pub fn is_bare_repository(path: &Path) -> bool {
+    !path.join(".git").exists() && path.join("HEAD").is_file() && path.join("objects").is_dir()
+}
+
+// Forcefully update an already-fetched repository by resetting to the upstream branch.
+// Returns descriptions of the local state preserved before resetting.
+async fn force_update(repo_path: &Path) -> Result<Vec<String>> {
     // Determine the upstream branch to hard reset against.
     match current_upstream(repo_path)
         .await
         .context("Unable to determine upstream branch for forced update")?
     {
         Some(upstream) => {
+            let preserved = preserve_local_state(repo_path, &upstream)
+                .await
+                .context("Failed to back up local state before forced update")?;
             // Reset hard to the upstream ref to drop local divergence or uncommitted changes.
-            run_git_command(["reset", "--hard", upstream.as_str()], Some(repo_path)).await
+            run_git_command(["reset", "--hard", upstream.as_str()], Some(repo_path)).await?;
+            Ok(preserved)
         }
         None => {
             // Empty repositories (no commits yet) have no upstream; nothing to reset.
-            Ok(())
+            Ok(Vec::new())
+        }
+    }
+}
+
+// Keep what a hard reset to `upstream` would discard: commits not contained in the upstream
+// go to `refs/replicant/backup/<stamp>` and uncommitted changes to tracked files to the stash.
+// (Untracked files survive the reset.) Shallow clones have no history to compare against and
+// explicitly trade local commits for size, so only their uncommitted changes are kept.
+async fn preserve_local_state(repo_path: &Path, upstream: &str) -> Result<Vec<String>> {
+    let mut preserved = Vec::new();
+    let stamp = manifest::utc_stamp();
+
+    let contained = run_git_command(
+        ["merge-base", "--is-ancestor", "HEAD", upstream],
+        Some(repo_path),
+    )
+    .await
+    .is_ok();
+    if !contained && !is_shallow_repository(repo_path).await {
+        let backup_ref = format!("{}/{}", BACKUP_REF_PREFIX, stamp);
+        run_git_command(["update-ref", backup_ref.as_str(), "HEAD"], Some(repo_path)).await?;
+        preserved.push(format!("local commits saved to {}", backup_ref));
+    }
+
+    let changes = run_git_command_output(
+        ["status", "--porcelain", "--untracked-files=no"],
+        Some(repo_path),
+    )
+    .await?;
+    if !changes.is_empty() {
+        let message = format!("replicant backup {}", stamp);
+        let mut args: Vec<&str> = Vec::new();
+        // Stashing records a commit; backup hosts often have no git identity configured.
+        if run_git_command_output(["var", "GIT_COMMITTER_IDENT"], Some(repo_path))
+            .await
+            .is_err()
+        {
+            args.extend([
+                "-c",
+                "user.name=GitHub Replicant",
+                "-c",
+                "user.email=replicant@localhost",
+            ]);
         }
+        args.extend(["stash", "push", "-m", message.as_str()]);
+        run_git_command(args, Some(repo_path)).await?;
+        preserved.push(format!("uncommitted changes stashed as \"{}\"", message));
     }
+
+    Ok(preserved)
 }
 
 // Resolve the current branch's upstream reference (e.g., origin/main).
@@ -206,11 +948,10 @@ async fn current_upstream(repo_path: &Path) -> Result<Option<String>> {
 
 // Detect whether the repository already contains commits.
 async fn has_commits(repo_path: &Path) -> Result<bool> {
-    let status = Command::new("git")
+    let status = git_command(Some(repo_path))
         .arg("rev-parse")
         .arg("--verify")
         .arg("HEAD")
-        .current_dir(repo_path)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
@@ -220,8 +961,8 @@ async fn has_commits(repo_path: &Path) -> Result<bool> {
     Ok(status.success())
 }
 
-// Clone the repository, handling DMCA errors gracefully.
-async fn clone_repository(repo: &Repo, repo_path: &Path) -> Result<()> {
+// Clone the repository with any extra clone flags, handling DMCA errors gracefully.
+async fn clone_repository(repo: &Repo, repo_path: &Path, extra_args: &[&str]) -> Result<()> {
     // If directory exists but no .git, remove it before cloning
     if repo_path.exists() {
         tokio::fs::remove_dir_all(repo_path)
@@ -234,7 +975,10 @@ async fn clone_repository(repo: &Repo, repo_path: &Path) -> Result<()> {
         .to_str()
         .ok_or_else(|| anyhow::anyhow!("Invalid destination path"))?;
 
-    let result = run_git_command(["clone", repo.clone_url.as_str(), path_str], None).await;
+    let mut args = vec!["clone"];
+    args.extend_from_slice(extra_args);
+    args.extend([repo.clone_url.as_str(), path_str]);
+    let result = run_git_command(args, None).await;
 
     // If clone fails, try to clean up the partially created directory
     if let Err(err) = &result {
@@ -258,8 +1002,293 @@ fn is_default_branch_error(err: &anyhow::Error) -> bool {
         && msg.contains("no such ref was fetched")
 }
 
+// Detect "repository not found" errors reported by git clone (HTTPS, SSH or local remotes).
+// Without credentials GitHub asks to authenticate instead of answering "not found", which fails
+// with "could not read Username" since terminal prompts are disabled.
+fn is_missing_repository_error(err: &anyhow::Error) -> bool {
+    let msg = err.to_string().to_lowercase();
+    msg.contains("repository not found")
+        || (msg.contains("repository '")
+            && (msg.contains("' not found") || msg.contains("' does not exist")))
+        || msg.contains("does not appear to be a git repository")
+        || msg.contains("could not read username")
+}
+
 // Detect DMCA-related errors.
 fn is_dmca_error(err: &anyhow::Error) -> bool {
     let msg = err.to_string().to_lowercase();
     msg.contains("dmca")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Run git synchronously with a fixed identity, returning trimmed stdout.
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@localhost"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(dir: &Path, file: &str, contents: &str) -> String {
+        std::fs::write(dir.join(file), contents).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-q", "-m", contents]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    // An upstream bare repository with one commit on `main`, a work tree used to push to it, and
+    // a clone of it (the backup). Returns (scratch dir, pusher, backup clone).
+    fn upstream_and_clone() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let scratch = tempfile::tempdir().unwrap();
+        let (pusher, backup) = (scratch.path().join("pusher"), scratch.path().join("backup"));
+        git(
+            scratch.path(),
+            &["init", "-q", "--bare", "-b", "main", "upstream.git"],
+        );
+        git(scratch.path(), &["clone", "-q", "upstream.git", "pusher"]);
+        git(&pusher, &["checkout", "-q", "-b", "main"]);
+        commit(&pusher, "f", "one");
+        git(&pusher, &["push", "-q", "origin", "main"]);
+        git(scratch.path(), &["clone", "-q", "upstream.git", "backup"]);
+        (scratch, pusher, backup)
+    }
+
+    fn snapshot(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, oid)| (name.to_string(), oid.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn ref_changes_between_snapshots() {
+        let before = snapshot(&[
+            ("refs/heads/a", "1"),
+            ("refs/heads/b", "2"),
+            ("refs/tags/t", "3"),
+        ]);
+        let after = snapshot(&[
+            ("refs/heads/a", "1"),
+            ("refs/heads/b", "9"),
+            ("refs/heads/c", "4"),
+        ]);
+        let changes = RefChanges::between(&before, &after);
+        assert_eq!((changes.added, changes.updated, changes.deleted), (1, 1, 1));
+        assert!(RefChanges::between(&after, &after).is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetch_all_refs_counts_branches_and_tags() {
+        let (_scratch, pusher, backup) = upstream_and_clone();
+        git(&pusher, &["checkout", "-q", "-b", "feature"]);
+        commit(&pusher, "g", "two");
+        git(&pusher, &["tag", "v1"]);
+        git(&pusher, &["push", "-q", "origin", "feature", "v1"]);
+
+        let (changes, overwritten) = fetch_all_refs(&backup, false, None).await.unwrap();
+        // origin/feature and tag v1 are new.
+        assert_eq!((changes.added, changes.updated, changes.deleted), (2, 0, 0));
+        assert!(overwritten.is_empty());
+
+        git(&pusher, &["push", "-q", "origin", "--delete", "feature"]);
+        let (changes, _) = fetch_all_refs(&backup, true, None).await.unwrap();
+        assert_eq!((changes.added, changes.updated, changes.deleted), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn forced_update_keeps_local_commits_and_changes() {
+        let (_scratch, pusher, backup) = upstream_and_clone();
+        let local = commit(&backup, "local", "mine");
+        std::fs::write(backup.join("f"), "edited").unwrap();
+        let upstream_tip = commit(&pusher, "g", "theirs");
+        git(&pusher, &["push", "-q", "origin", "main"]);
+        fetch_all_refs(&backup, true, None).await.unwrap();
+
+        let preserved = force_update(&backup).await.unwrap();
+
+        assert_eq!(preserved.len(), 2, "{:?}", preserved);
+        assert_eq!(git(&backup, &["rev-parse", "HEAD"]), upstream_tip);
+        let backups = git(
+            &backup,
+            &["for-each-ref", "--format=%(objectname)", BACKUP_REF_PREFIX],
+        );
+        assert_eq!(backups, local);
+        assert!(git(&backup, &["stash", "list"]).contains("replicant backup"));
+        assert!(git(&backup, &["stash", "show", "-p"]).contains("+edited"));
+    }
+
+    #[tokio::test]
+    async fn forced_update_without_local_state_preserves_nothing() {
+        let (_scratch, pusher, backup) = upstream_and_clone();
+        commit(&pusher, "g", "theirs");
+        git(&pusher, &["push", "-q", "origin", "main"]);
+        fetch_all_refs(&backup, true, None).await.unwrap();
+
+        assert!(force_update(&backup).await.unwrap().is_empty());
+        assert_eq!(git(&backup, &["for-each-ref", REPLICANT_REF_PREFIX]), "");
+        assert_eq!(git(&backup, &["stash", "list"]), "");
+    }
+
+    // A repository as listed by the API, cloned from a local path.
+    fn local_repo(clone_url: &Path) -> Repo {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "repo",
+            "full_name": "alice/repo",
+            "clone_url": clone_url.to_str().unwrap(),
+            "fork": false,
+            "private": false,
+            "has_wiki": false,
+            "has_issues": false,
+            "owner": { "login": "alice" },
+        }))
+        .unwrap()
+    }
+
+    // Replace the tip of `main` upstream with an unrelated commit. Returns (old tip, new tip).
+    fn force_push(pusher: &Path) -> (String, String) {
+        let old = git(pusher, &["rev-parse", "HEAD"]);
+        std::fs::write(pusher.join("f"), "rewritten").unwrap();
+        git(
+            pusher,
+            &["commit", "-q", "-a", "--amend", "-m", "rewritten"],
+        );
+        git(pusher, &["push", "-q", "-f", "origin", "main"]);
+        (old, git(pusher, &["rev-parse", "HEAD"]))
+    }
+
+    #[tokio::test]
+    async fn force_pushed_tips_are_kept_and_fast_forwards_are_not() {
+        let (_scratch, pusher, backup) = upstream_and_clone();
+        commit(&pusher, "g", "two");
+        git(&pusher, &["push", "-q", "origin", "main"]);
+        let (_, overwritten) = fetch_all_refs(&backup, false, None).await.unwrap();
+        assert!(overwritten.is_empty(), "{:?}", overwritten);
+
+        let (old, _) = force_push(&pusher);
+        let (_, overwritten) = fetch_all_refs(&backup, false, None).await.unwrap();
+
+        assert_eq!(overwritten.len(), 1, "{:?}", overwritten);
+        assert!(overwritten[0].starts_with(OVERWRITTEN_REF_PREFIX));
+        assert!(overwritten[0].ends_with("/remotes/origin/main"));
+        assert_eq!(git(&backup, &["rev-parse", overwritten[0].as_str()]), old);
+    }
+
+    #[tokio::test]
+    async fn working_copy_follows_a_force_push_without_local_commits() {
+        let (scratch, pusher, backup) = upstream_and_clone();
+        let repo = local_repo(&scratch.path().join("upstream.git"));
+        let (old, new) = force_push(&pusher);
+
+        let report = sync_repository(repo, &backup, SyncOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(git(&backup, &["rev-parse", "HEAD"]), new);
+        assert_eq!(report.overwritten.len(), 1);
+        assert_eq!(
+            git(&backup, &["rev-parse", report.overwritten[0].as_str()]),
+            old
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_update_after_force_push_names_the_kept_tips() {
+        let (scratch, pusher, backup) = upstream_and_clone();
+        let repo = local_repo(&scratch.path().join("upstream.git"));
+        std::fs::write(backup.join("f"), "dirty").unwrap();
+        force_push(&pusher);
+
+        let err = sync_repository(repo, &backup, SyncOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("previous tips kept at"), "{}", err);
+        assert_ne!(git(&backup, &["for-each-ref", OVERWRITTEN_REF_PREFIX]), "");
+        assert_eq!(std::fs::read_to_string(backup.join("f")).unwrap(), "dirty");
+    }
+
+    #[tokio::test]
+    async fn mirrors_ignore_rewritten_pull_request_refs() {
+        let (scratch, pusher, _backup) = upstream_and_clone();
+        let upstream = scratch.path().join("upstream.git");
+        let repo = local_repo(&upstream);
+        let mirror = scratch.path().join("mirror.git");
+        git(&upstream, &["update-ref", "refs/pull/1/merge", "main"]);
+        sync_mirror(&repo, &mirror, None).await.unwrap();
+
+        // GitHub recomputes merge refs; a rewrite there is not a force-push.
+        git(&pusher, &["checkout", "-q", "--orphan", "merge"]);
+        let merge = commit(&pusher, "m", "merge");
+        git(
+            &pusher,
+            &["push", "-q", "-f", "origin", "HEAD:refs/pull/1/merge"],
+        );
+        let report = sync_mirror(&repo, &mirror, None).await.unwrap();
+
+        assert!(report.overwritten.is_empty(), "{:?}", report.overwritten);
+        assert!(report.refs.is_empty());
+        assert_eq!(git(&mirror, &["rev-parse", "refs/pull/1/merge"]), merge);
+    }
+
+    #[tokio::test]
+    async fn reclone_carries_kept_refs_over() {
+        let (scratch, _pusher, backup) = upstream_and_clone();
+        let repo = local_repo(&scratch.path().join("upstream.git"));
+        let kept = format!("{}/20260101T000000Z", BACKUP_REF_PREFIX);
+        let local = commit(&backup, "local", "mine");
+        git(&backup, &["update-ref", kept.as_str(), local.as_str()]);
+
+        reclone_working_copy(&repo, &backup, &[]).await.unwrap();
+
+        assert_eq!(git(&backup, &["rev-parse", kept.as_str()]), local);
+        assert!(!files::sibling_path(&backup, ".reclone").exists());
+    }
+
+    #[test]
+    fn missing_repository_messages() {
+        let cases = [
+            ("remote: Repository not found.\nfatal: repository 'https://github.com/a/b.wiki.git/' not found", true),
+            ("fatal: repository '/tmp/missing.wiki.git' does not exist", true),
+            ("fatal: '/tmp/missing.git' does not appear to be a git repository", true),
+            ("fatal: could not read Username for 'https://github.com': terminal prompts disabled", true),
+            ("fatal: unable to access 'https://github.com/a/b.git/': Could not resolve host", false),
+            ("fatal: destination path 'b' already exists and is not an empty directory.", false),
+        ];
+        for (message, missing) in cases {
+            let err = anyhow::anyhow!("Git command failed: {}", message);
+            assert_eq!(is_missing_repository_error(&err), missing, "{}", message);
+        }
+    }
+
+    #[tokio::test]
+    async fn cloning_a_missing_local_remote_is_missing_repository() {
+        let dir = std::env::temp_dir().join(format!("replicant-missing-{}", std::process::id()));
+        let missing = dir.join("absent.wiki.git");
+        let err = run_git_command(
+            [
+                OsStr::new("clone"),
+                missing.as_os_str(),
+                dir.join("clone").as_os_str(),
+            ],
+            None,
+        )
+        .await
+        .unwrap_err();
+        tokio::fs::remove_dir_all(&dir).await.ok();
+        assert!(is_missing_repository_error(&err), "{}", err);
+    }
+}
@@ -2,24 +2,48 @@
 // github.rs
 // GitHub Replicant (Rust)
 //
-// Handles GitHub API interactions: paginated fetch helpers, repo/star/follower/following queries, and aggregation/deduplication of repositories with owner metadata for downstream syncing.
+// Handles GitHub API interactions: paginated fetch helpers, repo/org/authenticated/star/follower/following/gist queries, and aggregation/deduplication of repositories with owner metadata for downstream syncing.
 //
 // Thales Matheus Mendonça Santos - November 2025
 
+use crate::cache::{ApiCache, CachedPage};
+use crate::rate_limit::RateLimiter;
 use anyhow::{Context, Result};
-use reqwest::Client;
+use indicatif::ProgressBar;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK,
+};
+use reqwest::{Client, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Public GitHub API base URL, used unless `--api-url` points elsewhere.
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
 
 /// Relevant data from the GitHub API response.
 /// Includes owner info so we can build nested paths and deduplicate by full_name.
 #[derive(Debug, Deserialize, Clone)]
 pub struct Repo {
+    /// Stable GitHub id that survives renames and transfers (0 for gists, which have none).
+    #[serde(default)]
+    pub id: u64,
     pub name: String,
     pub clone_url: String,
+    /// SSH remote (`git@host:owner/name.git`), used with `--protocol ssh`.
+    #[serde(default)]
+    pub ssh_url: String,
     pub fork: bool,
+    pub private: bool,
+    pub has_wiki: bool,
+    pub has_issues: bool,
     pub full_name: String,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    /// Time of the last push to any branch (absent for gists).
+    #[serde(default)]
+    pub pushed_at: Option<String>,
     pub owner: Owner,
 }
 
@@ -33,78 +57,418 @@ struct User {
     login: String,
 }
 
+/// A gist as returned by the gists endpoints. Each gist is a small git repository.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Gist {
+    pub id: String,
+    pub description: Option<String>,
+    pub git_pull_url: String,
+    pub public: bool,
+    pub owner: Option<Owner>,
+    #[serde(default)]
+    pub files: HashMap<String, serde_json::Value>,
+}
+
+impl Gist {
+    /// View the gist as a repository named after its id, so it flows through the regular
+    /// clone/update pipeline. Anonymous gists are attributed to `fallback_owner`.
+    pub fn to_repo(&self, fallback_owner: &str) -> Repo {
+        let owner = self.owner.clone().unwrap_or_else(|| Owner {
+            login: fallback_owner.to_string(),
+        });
+        Repo {
+            id: 0,
+            name: self.id.clone(),
+            full_name: format!("{}/{}", owner.login, self.id),
+            clone_url: self.git_pull_url.clone(),
+            ssh_url: ssh_url_for(&self.git_pull_url).unwrap_or_default(),
+            fork: false,
+            private: !self.public,
+            has_wiki: false,
+            has_issues: false,
+            default_branch: None,
+            pushed_at: None,
+            owner,
+        }
+    }
+}
+
+// The gists API only returns HTTPS remotes; derive the SSH form
+// (`https://host/path.git` -> `git@host:path.git`).
+fn ssh_url_for(https_url: &str) -> Option<String> {
+    let (host, path) = https_url.strip_prefix("https://")?.split_once('/')?;
+    Some(format!("git@{}:{}", host, path))
+}
+
+/// GitHub API client shared by every fetcher.
+/// Bundles the HTTP client and API base URL with the progress bar used to report listing progress,
+/// the rate limiter that paces requests, and the optional ETag cache for listing pages.
+/// Clones share the rate limiter and cache.
+#[derive(Clone)]
+pub struct GitHubClient {
+    http: Client,
+    api_url: String,
+    progress: ProgressBar,
+    // Whether listings grow/advance the progress bar (only during the repository listing phase).
+    count_pages: bool,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Option<Arc<ApiCache>>,
+}
+
+impl GitHubClient {
+    pub fn new(
+        http: Client,
+        api_url: String,
+        progress: ProgressBar,
+        cache: Option<ApiCache>,
+    ) -> Self {
+        Self {
+            http,
+            api_url,
+            progress,
+            count_pages: true,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            cache: cache.map(Arc::new),
+        }
+    }
+
+    /// Client for per-repository API work during synchronization: rate-limit countdowns are
+    /// shown on `progress` without touching its position, and the listing cache is skipped
+    /// because incremental `since` URLs change on every run.
+    pub fn for_sync(&self, progress: ProgressBar) -> Self {
+        Self {
+            progress,
+            count_pages: false,
+            cache: None,
+            ..self.clone()
+        }
+    }
+
+    // Build an absolute API URL from a path such as `/users/{name}/repos`.
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.api_url, path)
+    }
+
+    /// GET through the rate limiter, which waits out limits and retries transient failures.
+    /// Used directly for non-JSON downloads such as release assets.
+    pub async fn get(&self, url: &str, headers: &HeaderMap) -> Result<Response> {
+        self.rate_limiter
+            .get(&self.http, url, headers, &self.progress)
+            .await
+    }
+
+    // Fetch a single page, sending cached validators and replaying the cached body on 304.
+    // Returns the JSON body and the raw `Link` header.
+    async fn get_page(&self, url: &str) -> Result<(String, Option<String>), PageError> {
+        let cached = match &self.cache {
+            Some(cache) => cache.load(url).await,
+            None => None,
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Some(entry) = &cached {
+            if let Some(etag) = entry
+                .etag
+                .as_deref()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = entry
+                .last_modified
+                .as_deref()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let response = self.get(url, &headers).await.map_err(PageError::Connect)?;
+        let status = response.status();
+
+        // 304 Not Modified: the cached page is still current and the request is free.
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = cached {
+                return Ok((entry.body, entry.link));
+            }
+        }
+
+        if !status.is_success() {
+            return Err(PageError::Status(status));
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let link = header(LINK);
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| PageError::Body(e.into()))?;
+
+        if let Some(cache) = &self.cache {
+            if etag.is_some() || last_modified.is_some() {
+                let entry = CachedPage {
+                    url: url.to_string(),
+                    etag,
+                    last_modified,
+                    link: link.clone(),
+                    body,
+                };
+                // Caching is best-effort; a failed write only costs a full fetch next time.
+                cache.store(&entry).await.ok();
+                return Ok((entry.body, link));
+            }
+        }
+
+        Ok((body, link))
+    }
+}
+
+// Failure modes of a single page fetch, so the caller can attach page/context details.
+enum PageError {
+    Connect(anyhow::Error),
+    Status(StatusCode),
+    Body(anyhow::Error),
+}
+
+impl PageError {
+    // Build the user-facing error for a failed page of `context_label`.
+    fn into_error(self, page: u64, context_label: &str) -> anyhow::Error {
+        match self {
+            PageError::Connect(err) => err.context(format!(
+                "Failed to connect to GitHub API on page {} for {}",
+                page, context_label
+            )),
+            PageError::Status(status) => anyhow::anyhow!(
+                "GitHub API error {} while fetching {}.",
+                status,
+                context_label
+            ),
+            PageError::Body(err) => err.context(format!(
+                "Failed to read GitHub API response for {}",
+                context_label
+            )),
+        }
+    }
+}
+
+/// Normalizes a user-supplied API base URL.
+/// `https://github.com` maps to the public API; a bare GitHub Enterprise Server host such as
+/// `https://ghe.example.com` gets the `/api/v3` prefix; any URL with an explicit path
+/// (e.g. `http://localhost:8080/api/v3`) is used verbatim. Trailing slashes are dropped.
+pub fn normalize_api_url(raw: &str) -> Result<String> {
+    let trimmed = raw.trim().trim_end_matches('/');
+    let (scheme, rest) = trimmed
+        .split_once("://")
+        .ok_or_else(|| anyhow::anyhow!("API URL must start with http:// or https://: {}", raw))?;
+    if scheme != "http" && scheme != "https" {
+        return Err(anyhow::anyhow!(
+            "API URL must start with http:// or https://: {}",
+            raw
+        ));
+    }
+
+    let (host, path) = match rest.split_once('/') {
+        Some((host, path)) => (host, path),
+        None => (rest, ""),
+    };
+    if host.is_empty() {
+        return Err(anyhow::anyhow!("API URL is missing a host: {}", raw));
+    }
+
+    if !path.is_empty() {
+        return Ok(trimmed.to_string());
+    }
+
+    if host.eq_ignore_ascii_case("github.com") || host.eq_ignore_ascii_case("api.github.com") {
+        Ok(DEFAULT_API_URL.to_string())
+    } else {
+        Ok(format!("{}://{}/api/v3", scheme, host))
+    }
+}
+
+/// Git hosts (`scheme://host`) served by the GitHub instance behind a normalized API URL.
+/// github.com serves code and gists from separate hosts; Enterprise Server serves both
+/// from the API host.
+pub fn git_hosts(api_url: &str) -> Vec<String> {
+    let (scheme, rest) = api_url.split_once("://").unwrap_or(("https", api_url));
+    let host = rest.split('/').next().unwrap_or(rest);
+    if host.eq_ignore_ascii_case("api.github.com") {
+        vec![
+            "https://github.com".to_string(),
+            "https://gist.github.com".to_string(),
+        ]
+    } else {
+        vec![format!("{}://{}", scheme, host)]
+    }
+}
+
 /// Generic helper to fetch paginated GitHub resources.
-/// Accepts a URL builder for each page and a label used in error messages.
-async fn fetch_paginated<T, F>(client: &Client, build_url: F, context_label: &str) -> Result<Vec<T>>
+/// Starts at `first_url` and follows the `Link: rel="next"` header until the last page;
+/// `rel="last"` on the first page is used to grow the progress bar by the expected page count.
+async fn fetch_paginated<T>(
+    client: &GitHubClient,
+    first_url: String,
+    context_label: &str,
+) -> Result<Vec<T>>
 where
     T: DeserializeOwned,
-    F: Fn(usize) -> String,
 {
     let mut items = Vec::new();
-    let mut page = 1;
+    let mut next_url = Some(first_url);
+    let mut page: u64 = 1;
+    let mut estimated_pages: u64 = 0;
+
+    if client.count_pages {
+        client.progress.set_message(format!("📄 {}", context_label));
+    }
 
-    loop {
-        let url = build_url(page);
+    while let Some(url) = next_url.take() {
+        let (body, link) = client
+            .get_page(&url)
+            .await
+            .map_err(|err| err.into_error(page, context_label))?;
 
-        let response = client.get(&url).send().await.with_context(|| {
-            format!(
-                "Failed to connect to GitHub API on page {} for {}",
-                page, context_label
-            )
-        })?;
+        let links = link.as_deref().map(parse_link_header).unwrap_or_default();
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "GitHub API error {} while fetching {}.",
-                response.status(),
-                context_label
-            ));
+        // The first page tells us how many pages to expect; listings that grow while we page
+        // through them extend the estimate one page at a time.
+        if client.count_pages {
+            if page == 1 {
+                estimated_pages = links
+                    .get("last")
+                    .and_then(|last| page_number(last))
+                    .unwrap_or(1);
+                client.progress.inc_length(estimated_pages);
+            } else if page > estimated_pages {
+                estimated_pages += 1;
+                client.progress.inc_length(1);
+            }
         }
 
-        let page_items: Vec<T> = response.json().await.with_context(|| {
+        let page_items: Vec<T> = serde_json::from_str(&body).with_context(|| {
             format!(
                 "Failed to parse GitHub API JSON response for {}",
                 context_label
             )
         })?;
 
-        // GitHub pagination ends when a page returns an empty array.
-        if page_items.is_empty() {
-            break;
+        items.extend(page_items);
+        if client.count_pages {
+            client.progress.inc(1);
         }
 
-        items.extend(page_items);
+        // Pagination ends when GitHub no longer advertises a next page.
+        next_url = links.get("next").cloned();
         page += 1;
     }
 
     Ok(items)
 }
 
+/// Parses an RFC 5988 `Link` header into a map of `rel` -> URL.
+/// Example: `<https://api.github.com/...&page=2>; rel="next", <...&page=5>; rel="last"`.
+fn parse_link_header(value: &str) -> HashMap<String, String> {
+    let mut links = HashMap::new();
+    let mut rest = value;
+
+    // Walk `<url>` entries rather than splitting on commas, since URLs may contain commas
+    // (e.g. `affiliation=owner,collaborator`).
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let url = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+
+        // Parameters run until the next link entry.
+        let params_end = rest.find('<').unwrap_or(rest.len());
+        for param in rest[..params_end].split(';') {
+            let param = param.trim().trim_end_matches(',').trim();
+            if let Some((key, rel)) = param.split_once('=') {
+                if key.trim() == "rel" {
+                    // A single rel may carry several space-separated relation types.
+                    for rel in rel.trim().trim_matches('"').split_whitespace() {
+                        links.insert(rel.to_string(), url.to_string());
+                    }
+                }
+            }
+        }
+        rest = &rest[params_end..];
+    }
+
+    links
+}
+
+// Extract the `page` query parameter from a pagination URL.
+fn page_number(url: &str) -> Option<u64> {
+    let query = url.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
 /// Fetches all repositories for the user, handling GitHub API pagination.
-pub async fn fetch_all_repos(client: &Client, username: &str) -> Result<Vec<Repo>> {
+pub async fn fetch_all_repos(client: &GitHubClient, username: &str) -> Result<Vec<Repo>> {
     fetch_paginated(
         client,
-        |page| {
-            format!(
-                "https://api.github.com/users/{}/repos?per_page=100&page={}",
-                username, page
-            )
-        },
+        client.endpoint(&format!("/users/{}/repos?per_page=100", username)),
         &format!("repositories for user {}", username),
     )
     .await
 }
 
+/// Fetches all repositories of an organization, filtered by the GitHub `type` parameter
+/// (all, public, private, forks, sources, member).
+pub async fn fetch_org_repos(
+    client: &GitHubClient,
+    org: &str,
+    repo_type: &str,
+) -> Result<Vec<Repo>> {
+    fetch_paginated(
+        client,
+        client.endpoint(&format!(
+            "/orgs/{}/repos?type={}&per_page=100",
+            org, repo_type
+        )),
+        &format!("repositories for organization {}", org),
+    )
+    .await
+}
+
+/// Fetches every repository the authenticated user can access via `/user/repos`,
+/// including private repos and repos shared through collaboration or org membership.
+pub async fn fetch_authenticated_repos(
+    client: &GitHubClient,
+    affiliation: &str,
+    visibility: &str,
+) -> Result<Vec<Repo>> {
+    fetch_paginated(
+        client,
+        client.endpoint(&format!(
+            "/user/repos?affiliation={}&visibility={}&per_page=100",
+            affiliation, visibility
+        )),
+        "repositories for the authenticated user",
+    )
+    .await
+}
+
 /// Fetches all repositories starred by the user.
-pub async fn fetch_starred_repos(client: &Client, username: &str) -> Result<Vec<Repo>> {
+pub async fn fetch_starred_repos(client: &GitHubClient, username: &str) -> Result<Vec<Repo>> {
     fetch_paginated(
         client,
-        |page| {
-            format!(
-                "https://api.github.com/users/{}/starred?per_page=100&page={}",
-                username, page
-            )
-        },
+        client.endpoint(&format!("/users/{}/starred?per_page=100", username)),
         &format!("starred repositories for user {}", username),
     )
     .await
@@ -114,7 +478,7 @@ pub async fn fetch_starred_repos(client: &Client, username: &str) -> Result<Vec<
 /// Uses the authenticated `/user/subscriptions` endpoint to include both "Watching"
 /// and "Custom" notification settings. Falls back to public endpoint if not authenticated.
 pub async fn fetch_watched_repos(
-    client: &Client,
+    client: &GitHubClient,
     username: &str,
     is_authenticated: bool,
 ) -> Result<Vec<Repo>> {
@@ -122,12 +486,7 @@ pub async fn fetch_watched_repos(
         // Authenticated endpoint returns ALL subscriptions including "Custom" notifications
         fetch_paginated(
             client,
-            |page| {
-                format!(
-                    "https://api.github.com/user/subscriptions?per_page=100&page={}",
-                    page
-                )
-            },
+            client.endpoint("/user/subscriptions?per_page=100"),
             "watched repositories (authenticated)",
         )
         .await
@@ -135,28 +494,85 @@ pub async fn fetch_watched_repos(
         // Public endpoint only returns repos with "Watching" (All Activity) enabled
         fetch_paginated(
             client,
-            |page| {
-                format!(
-                    "https://api.github.com/users/{}/subscriptions?per_page=100&page={}",
-                    username, page
-                )
-            },
+            client.endpoint(&format!("/users/{}/subscriptions?per_page=100", username)),
             &format!("watched repositories for user {}", username),
         )
         .await
     }
 }
 
+/// Fetches gists for the user. When authenticated, uses `/gists` so the authenticated user's
+/// secret gists are included; otherwise only the user's public gists are returned.
+pub async fn fetch_gists(
+    client: &GitHubClient,
+    username: &str,
+    is_authenticated: bool,
+) -> Result<Vec<Gist>> {
+    if is_authenticated {
+        fetch_paginated(
+            client,
+            client.endpoint("/gists?per_page=100"),
+            "gists (authenticated)",
+        )
+        .await
+    } else {
+        fetch_paginated(
+            client,
+            client.endpoint(&format!("/users/{}/gists?per_page=100", username)),
+            &format!("gists for user {}", username),
+        )
+        .await
+    }
+}
+
+/// Fetches every item of a per-repository collection such as `issues?state=all` or `labels`,
+/// optionally limited to items updated at or after the ISO 8601 `since` timestamp.
+pub async fn fetch_repo_items(
+    client: &GitHubClient,
+    full_name: &str,
+    resource: &str,
+    since: Option<&str>,
+) -> Result<Vec<serde_json::Value>> {
+    let separator = if resource.contains('?') { '&' } else { '?' };
+    let mut path = format!("/repos/{}/{}{}per_page=100", full_name, resource, separator);
+    if let Some(since) = since {
+        path.push_str(&format!("&since={}", since));
+    }
+
+    fetch_paginated(
+        client,
+        client.endpoint(&path),
+        &format!("{} of {}", resource, full_name),
+    )
+    .await
+}
+
+/// Fetches a single per-repository resource such as `pulls/42`.
+pub async fn fetch_repo_item(
+    client: &GitHubClient,
+    full_name: &str,
+    resource: &str,
+) -> Result<serde_json::Value> {
+    let context_label = format!("{} of {}", resource, full_name);
+    let url = client.endpoint(&format!("/repos/{}/{}", full_name, resource));
+    let (body, _) = client
+        .get_page(&url)
+        .await
+        .map_err(|err| err.into_error(1, &context_label))?;
+
+    serde_json::from_str(&body).with_context(|| {
+        format!(
+            "Failed to parse GitHub API JSON response for {}",
+            context_label
+        )
+    })
+}
+
 /// Fetches the list of usernames this profile follows.
-pub async fn fetch_following_users(client: &Client, username: &str) -> Result<Vec<String>> {
+pub async fn fetch_following_users(client: &GitHubClient, username: &str) -> Result<Vec<String>> {
     let users: Vec<User> = fetch_paginated(
         client,
-        |page| {
-            format!(
-                "https://api.github.com/users/{}/following?per_page=100&page={}",
-                username, page
-            )
-        },
+        client.endpoint(&format!("/users/{}/following?per_page=100", username)),
         &format!("following list for user {}", username),
     )
     .await?;
@@ -165,15 +581,10 @@ pub async fn fetch_following_users(client: &Client, username: &str) -> Result<Ve
 }
 
 /// Fetches the list of usernames that follow this profile.
-pub async fn fetch_followers(client: &Client, username: &str) -> Result<Vec<String>> {
+pub async fn fetch_followers(client: &GitHubClient, username: &str) -> Result<Vec<String>> {
     let users: Vec<User> = fetch_paginated(
         client,
-        |page| {
-            format!(
-                "https://api.github.com/users/{}/followers?per_page=100&page={}",
-                username, page
-            )
-        },
+        client.endpoint(&format!("/users/{}/followers?per_page=100", username)),
         &format!("followers list for user {}", username),
     )
     .await?;
@@ -182,7 +593,10 @@ pub async fn fetch_followers(client: &Client, username: &str) -> Result<Vec<Stri
 }
 
 /// Fetch all repositories for a list of usernames, deduplicating by full name.
-pub async fn fetch_repos_for_users(client: &Client, usernames: &[String]) -> Result<Vec<Repo>> {
+pub async fn fetch_repos_for_users(
+    client: &GitHubClient,
+    usernames: &[String],
+) -> Result<Vec<Repo>> {
     let mut repos_by_full_name = HashMap::new();
     let mut seen_users = HashSet::new();
 
@@ -210,3 +624,87 @@ pub async fn fetch_repos_for_users(client: &Client, usernames: &[String]) -> Res
 
     Ok(repos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_link_header_cases() {
+        let cases: &[(&str, &[(&str, &str)])] = &[
+            ("", &[]),
+            (
+                r#"<https://api.github.com/user/repos?page=2>; rel="next", <https://api.github.com/user/repos?page=5>; rel="last""#,
+                &[
+                    ("next", "https://api.github.com/user/repos?page=2"),
+                    ("last", "https://api.github.com/user/repos?page=5"),
+                ],
+            ),
+            // Commas inside URLs do not split entries.
+            (
+                r#"<https://api.github.com/user/repos?affiliation=owner,collaborator&page=2>; rel="next""#,
+                &[(
+                    "next",
+                    "https://api.github.com/user/repos?affiliation=owner,collaborator&page=2",
+                )],
+            ),
+            // Several relation types on one link, unquoted values and extra parameters.
+            (
+                r#"<https://x/?page=1>; rel="first prev"; title="start", <https://x/?page=3>;rel=next"#,
+                &[
+                    ("first", "https://x/?page=1"),
+                    ("prev", "https://x/?page=1"),
+                    ("next", "https://x/?page=3"),
+                ],
+            ),
+            // An unterminated entry is ignored.
+            (r#"<https://x/?page=2; rel="next""#, &[]),
+        ];
+
+        for (header, expected) in cases {
+            let expected: HashMap<String, String> = expected
+                .iter()
+                .map(|(rel, url)| (rel.to_string(), url.to_string()))
+                .collect();
+            assert_eq!(parse_link_header(header), expected, "header: {}", header);
+        }
+    }
+
+    #[test]
+    fn normalize_api_url_cases() {
+        let cases = [
+            ("https://api.github.com", Some(DEFAULT_API_URL)),
+            ("https://api.github.com/", Some(DEFAULT_API_URL)),
+            ("https://github.com", Some(DEFAULT_API_URL)),
+            ("  https://GitHub.com/  ", Some(DEFAULT_API_URL)),
+            // A bare GitHub Enterprise host gets the REST prefix.
+            (
+                "https://ghe.example.com",
+                Some("https://ghe.example.com/api/v3"),
+            ),
+            ("http://10.0.0.5:8080/", Some("http://10.0.0.5:8080/api/v3")),
+            // Explicit paths are kept as given.
+            (
+                "https://ghe.example.com/api/v3/",
+                Some("https://ghe.example.com/api/v3"),
+            ),
+            (
+                "https://proxy.example.com/github",
+                Some("https://proxy.example.com/github"),
+            ),
+            ("ghe.example.com", None),
+            ("ftp://ghe.example.com", None),
+            ("https://", None),
+            ("https:///api/v3", None),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(
+                normalize_api_url(raw).ok().as_deref(),
+                expected,
+                "url: {}",
+                raw
+            );
+        }
+    }
+}
@@ -2,16 +2,24 @@
 // main.rs
 // GitHub Replicant (Rust)
 //
-// Coordinates the CLI workflow: selects the sync source (own, stars, followers, following), fetches repositories from GitHub, filters them, and orchestrates concurrent git clone/pull operations with progress reporting.
+// Coordinates the CLI workflow: selects the sync source (own, stars, followers, following, watching, org, mine, gists), fetches repositories from GitHub, filters them, and orchestrates concurrent git clone/pull operations with progress reporting.
 //
 // Thales Matheus Mendonça Santos - November 2025
 
 mod args;
+mod cache;
+mod files;
 mod git;
 mod github;
+mod manifest;
+mod metadata;
+mod plan;
+mod rate_limit;
+mod releases;
+mod trash;
 
 use anyhow::{Context, Result};
-use args::Cli;
+use args::{Cli, OrgRepoType, Protocol, PruneLimit};
 use clap::Parser;
 use futures::stream::{self, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
@@ -22,6 +30,11 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Minimum time between manifest saves while repositories are syncing. Rewriting it after every
+/// repository would cost O(n²) for large accounts; the run always ends with a full save.
+const MANIFEST_SAVE_INTERVAL: Duration = Duration::from_secs(30);
 
 // Data source selector. Only one flag is allowed at a time.
 enum SyncSource {
@@ -30,6 +43,9 @@ enum SyncSource {
     Following,
     Followers,
     Watching,
+    Org,
+    Mine,
+    Gists,
 }
 
 #[tokio::main]
@@ -46,10 +62,29 @@ async fn main() -> Result<()> {
         SyncSource::Followers
     } else if args.watching {
         SyncSource::Watching
+    } else if args.org {
+        SyncSource::Org
+    } else if args.mine {
+        SyncSource::Mine
+    } else if args.gists {
+        SyncSource::Gists
     } else {
         SyncSource::Own
     };
 
+    // The /user/repos endpoint only exists for authenticated requests.
+    if matches!(source, SyncSource::Mine) && args.token.is_none() {
+        return Err(anyhow::anyhow!(
+            "--mine requires a GitHub token (--token or GITHUB_TOKEN)."
+        ));
+    }
+
+    // Restoring from the quarantine only needs the output folder, not the API.
+    if let Some(entry) = &args.restore {
+        let output_dir = PathBuf::from("output").join(output_dir_name(username, &source));
+        return restore_from_trash(&output_dir, entry).await;
+    }
+
     // The GitHub API requires a valid User-Agent; include Authorization when provided.
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -63,10 +98,42 @@ async fn main() -> Result<()> {
         headers.insert(AUTHORIZATION, header_value);
     }
 
-    let client = Client::builder()
-        .default_headers(headers)
-        .build()
-        .context("Failed to build HTTP client")?;
+    let api_url = github::normalize_api_url(&args.api_url)?;
+    let git_hosts = github::git_hosts(&api_url);
+
+    let mut builder = Client::builder().default_headers(headers);
+    // GitHub Enterprise Server instances are often signed by an internal CA.
+    if let Some(ca_path) = args.ca_cert.as_deref() {
+        let pem = tokio::fs::read(ca_path)
+            .await
+            .with_context(|| format!("Failed to read CA certificate file {:?}", ca_path))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid PEM certificate in {:?}", ca_path))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    let http = builder.build().context("Failed to build HTTP client")?;
+
+    // Listing progress: the page count grows as GitHub's Link headers reveal each listing's size.
+    let fetch_pb = ProgressBar::new(0);
+    fetch_pb.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} pages {msg}",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+    // Listing pages are cached with their ETags so unchanged pages come back as free 304s.
+    let cache = if args.no_cache {
+        None
+    } else {
+        Some(cache::ApiCache::new(PathBuf::from("output").join(".cache")))
+    };
+    let client = github::GitHubClient::new(http, api_url, fetch_pb.clone(), cache);
+
+    // Gist descriptions are kept for the human-readable index written next to the clones.
+    let mut gists: Vec<github::Gist> = Vec::new();
 
     // Fetch the requested repo set based on the selected source.
     let (all_repos, source_label) = match source {
@@ -85,6 +152,7 @@ async fn main() -> Result<()> {
             let following = github::fetch_following_users(&client, username).await?;
 
             if following.is_empty() {
+                fetch_pb.finish_and_clear();
                 println!("ℹ️ No following accounts found for {}.", username);
                 return Ok(());
             }
@@ -105,6 +173,7 @@ async fn main() -> Result<()> {
             let followers = github::fetch_followers(&client, username).await?;
 
             if followers.is_empty() {
+                fetch_pb.finish_and_clear();
                 println!("ℹ️ No followers found for {}.", username);
                 return Ok(());
             }
@@ -132,13 +201,53 @@ async fn main() -> Result<()> {
             let repos = github::fetch_watched_repos(&client, username, is_authenticated).await?;
             (repos, format!("watched repositories of {}", username))
         }
+        SyncSource::Org => {
+            let repo_type = args.org_type.as_query();
+            println!(
+                "🔍 Fetching {} repositories for organization: {}",
+                repo_type, username
+            );
+            let repos = github::fetch_org_repos(&client, username, repo_type).await?;
+            (repos, format!("repositories of organization {}", username))
+        }
+        SyncSource::Mine => {
+            let affiliation = args
+                .affiliation
+                .iter()
+                .map(|a| a.as_query())
+                .collect::<Vec<_>>()
+                .join(",");
+            let visibility = args.visibility.as_query();
+            println!(
+                "🔍 Fetching {} repositories ({}) for authenticated user",
+                visibility, affiliation
+            );
+            let repos =
+                github::fetch_authenticated_repos(&client, &affiliation, visibility).await?;
+            (repos, format!("repositories accessible to {}", username))
+        }
+        SyncSource::Gists => {
+            let is_authenticated = args.token.is_some();
+            if is_authenticated {
+                println!("🔍 Fetching gists (including secret) for authenticated user");
+            } else {
+                println!("🔍 Fetching gists for: {}", username);
+            }
+            gists = github::fetch_gists(&client, username, is_authenticated).await?;
+            let repos = gists.iter().map(|g| g.to_repo(username)).collect();
+            (repos, format!("gists of {}", username))
+        }
     };
 
-    // Drop forks unless explicitly requested.
+    fetch_pb.finish_and_clear();
+
+    // Drop forks unless explicitly requested (asking an org for its forks implies keeping them).
+    let include_forks = args.include_forks
+        || (matches!(source, SyncSource::Org) && args.org_type == OrgRepoType::Forks);
     let repos_to_sync: Vec<github::Repo> = all_repos
         .clone()
         .into_iter()
-        .filter(|r| args.include_forks || !r.fork)
+        .filter(|r| include_forks || !r.fork)
         .collect();
 
     // Compute the target output folder name based on the source type.
@@ -148,7 +257,13 @@ async fn main() -> Result<()> {
     // Pre-compute the desired destination paths for mirroring and sync.
     let desired_paths: HashSet<PathBuf> = repos_to_sync
         .iter()
-        .map(|repo| destination_path(&output_dir, repo, username))
+        .flat_map(|repo| {
+            let path = destination_path(&output_dir, repo, username);
+            // Keep wiki clones when they are part of the backup.
+            let wiki = (args.include_wikis && repo.has_wiki)
+                .then(|| files::sibling_path(&path, git::WIKI_SUFFIX));
+            std::iter::once(path).chain(wiki)
+        })
         .collect();
 
     let count = repos_to_sync.len();
@@ -159,13 +274,38 @@ async fn main() -> Result<()> {
         source_label
     );
 
+    // Guard against a truncated listing pruning most of the backup.
+    let prune_limit = (!args.yes_prune_all).then_some(args.max_prune);
+
+    // Report what would happen without touching any repository.
+    if args.dry_run {
+        let manifest = manifest::Manifest::load(&output_dir).await;
+        let plan = build_plan(
+            &args,
+            &output_dir,
+            &manifest,
+            &repos_to_sync,
+            &desired_paths,
+        )
+        .await?;
+        plan.print();
+        if let Some(path) = &args.plan_json {
+            plan.write_json(path).await?;
+        }
+        return Ok(());
+    }
+
     if count == 0 {
         // Allow --exact-mirror to clean up when there are no repos to sync.
         if args.exact_mirror {
             tokio::fs::create_dir_all(&output_dir)
                 .await
                 .with_context(|| format!("Failed to create output directory: {:?}", output_dir))?;
-            prune_extra_repos(&output_dir, &desired_paths).await?;
+            let pruned = prune_extra_repos(&output_dir, &desired_paths, prune_limit).await?;
+            purge_trash(&output_dir, args.trash_retention).await?;
+            if !pruned {
+                return Err(anyhow::anyhow!("Pruning refused by --max-prune."));
+            }
         }
         return Ok(());
     }
@@ -176,6 +316,16 @@ async fn main() -> Result<()> {
         .await
         .with_context(|| format!("Failed to create output directory: {:?}", output_dir))?;
 
+    if !gists.is_empty() {
+        write_gist_index(&output_dir, &gists, username).await?;
+    }
+
+    // Repositories renamed or transferred on GitHub get a new destination path; move the
+    // existing clone there (matched by id) instead of cloning it again.
+    let manifest = manifest::Manifest::load(&output_dir).await;
+    relocate_renamed_repos(&output_dir, &manifest, &repos_to_sync, username).await?;
+    let listed_ids: HashSet<u64> = repos_to_sync.iter().map(|repo| repo.id).collect();
+
     // Progress Bar Configuration
     let pb = ProgressBar::new(count as u64);
     pb.set_style(
@@ -190,43 +340,251 @@ async fn main() -> Result<()> {
     // Use Arc to safely share the output directory across tasks without cloning paths.
     let output_dir_arc = Arc::new(output_dir);
     let root_username = username.clone();
-    let force_update = args.force;
+
+    // git runs GIT_SSH_COMMAND through a shell, so quote the key path.
+    let ssh_command = match &args.ssh_key {
+        Some(key) => Some(format!(
+            "ssh -i '{}' -o IdentitiesOnly=yes",
+            key.display().to_string().replace('\'', "'\\''")
+        )),
+        None => args.ssh_command.clone(),
+    };
+    let mut git_env = Vec::new();
+    if let Some(ssh_command) = ssh_command {
+        git_env.push(("GIT_SSH_COMMAND".to_string(), ssh_command));
+    }
+    // Let git use the token for HTTPS remotes on this GitHub instance (private repos, gists).
+    if let Some(token) = args.token.as_deref() {
+        git_env.extend(git::token_environment(token, &git_hosts));
+    }
+    git::set_git_environment(git_env);
+
+    let sync_options = git::SyncOptions {
+        force_reset: args.force,
+        mirror: args.mirror,
+        prune: args.prune,
+        lfs: args.lfs,
+        submodules: args.submodules,
+        depth: args.depth,
+        filter: args.filter.map(|filter| filter.as_arg()),
+        ssh: args.protocol == Protocol::Ssh,
+    };
+    let include_wikis = args.include_wikis;
+    let export_issues = args.issues;
+    let backup_releases = args.releases;
+    let full_check = args.full;
+    // API work during sync reports rate-limit waits on the sync progress bar.
+    let sync_client = client.for_sync(pb.clone());
+    // Updated after every repository and saved periodically (with the time of the last save) so
+    // an interrupted run still leaves a recent record.
+    let manifest = tokio::sync::Mutex::new((manifest, Instant::now()));
 
     let stream = stream::iter(repos_to_sync)
         .map(|repo| {
             let base_dir_clone = Arc::clone(&output_dir_arc);
             let pb_clone = pb.clone();
             let repo_name = repo.name.clone();
+            let is_private = repo.private;
             let root_username = root_username.clone();
-            let force_update = force_update;
+            let sync_client = &sync_client;
+            let manifest = &manifest;
             // Create an async task for each repository
             async move {
                 pb_clone.set_message(format!("🔄 {}", repo.name));
                 // Compute destination path respecting owner to avoid collisions.
                 let destination = destination_path(base_dir_clone.as_ref(), &repo, &root_username);
-                let result = git::sync_repository(repo.clone(), &destination, force_update).await;
+                let started = manifest::utc_now();
+                // Skip git network operations for repositories nothing was pushed to since the
+                // last successful sync, unless --full asks to check everything. Clones whose
+                // origin needs repointing (renames, --protocol changes) are still synced.
+                let unchanged = !full_check
+                    && manifest.lock().await.0.is_unchanged(&repo)
+                    && is_git_repo(&destination).await
+                    && git::origin_is_current(&repo, &destination, sync_options.ssh).await;
+                let mut result = if unchanged {
+                    Ok(git::SyncReport {
+                        unchanged: true,
+                        ..Default::default()
+                    })
+                } else {
+                    git::sync_repository(repo.clone(), &destination, sync_options).await
+                };
+
+                // Wikis live in a separate `<repo>.wiki.git` remote; a failure there is reported
+                // as a warning rather than failing the repository itself.
+                if let Ok(report) = result.as_mut() {
+                    if include_wikis && repo.has_wiki {
+                        match git::sync_wiki(&repo, &destination, sync_options).await {
+                            Ok(synced) => report.wiki_synced = synced,
+                            Err(e) => report.warnings.push(format!("wiki: {}", e)),
+                        }
+                    }
+
+                    // Discussion history is exported next to the clone; like wikis, a failure
+                    // here does not fail the repository.
+                    if export_issues {
+                        match metadata::export_issues(sync_client, &repo, &destination).await {
+                            Ok(count) => report.issues_exported = count,
+                            Err(e) => report.warnings.push(format!("issues: {:#}", e)),
+                        }
+                    }
+
+                    if backup_releases {
+                        match releases::backup_releases(sync_client, &repo, &destination).await {
+                            Ok(count) => report.release_assets_downloaded = count,
+                            Err(e) => report.warnings.push(format!("releases: {:#}", e)),
+                        }
+                    }
+                }
+
+                {
+                    let mut state = manifest.lock().await;
+                    let (manifest, last_save) = &mut *state;
+                    let relative = destination_path(Path::new(""), &repo, &root_username);
+                    manifest.record_sync(&repo, relative, started, &result);
+                    if last_save.elapsed() >= MANIFEST_SAVE_INTERVAL {
+                        // A failed save here is retried by the final save after the run.
+                        manifest.save(base_dir_clone.as_ref()).await.ok();
+                        *last_save = Instant::now();
+                    }
+                }
                 pb_clone.inc(1);
-                (repo_name, result)
+                (repo_name, is_private, result)
             }
         })
         // Control how many tasks run simultaneously
         .buffer_unordered(args.concurrency);
 
     // Execute stream and collect results
-    let results: Vec<(String, Result<()>)> = stream.collect().await;
+    let results: Vec<(String, bool, Result<git::SyncReport>)> = stream.collect().await;
 
     pb.finish_with_message("🎉 Synchronization complete!");
 
     // If requested, remove repositories not present in the latest fetch.
+    let mut prune_refused = false;
     if args.exact_mirror {
-        prune_extra_repos(output_dir_arc.as_ref(), &desired_paths).await?;
+        prune_refused =
+            !prune_extra_repos(output_dir_arc.as_ref(), &desired_paths, prune_limit).await?;
+    }
+    purge_trash(output_dir_arc.as_ref(), args.trash_retention).await?;
+
+    // Forget repositories that are neither listed anymore nor present on disk.
+    let (mut manifest, _) = manifest.into_inner();
+    manifest
+        .repos
+        .retain(|id, record| listed_ids.contains(id) || output_dir_arc.join(&record.path).exists());
+    manifest.save(output_dir_arc.as_ref()).await?;
+
+    let unchanged = results
+        .iter()
+        .filter(|(_, _, res)| matches!(res, Ok(report) if report.unchanged))
+        .count();
+    if unchanged > 0 {
+        println!(
+            "⏭️ {} unchanged repositories skipped (nothing pushed since the last sync; use --full to check them).",
+            unchanged
+        );
+    }
+
+    // Per-repo ref changes picked up by fetching every branch and tag.
+    for (name, _, result) in &results {
+        if let Ok(report) = result {
+            if !report.refs.is_empty() {
+                println!(
+                    "🔀 {}: {} refs added, {} updated, {} deleted",
+                    name, report.refs.added, report.refs.updated, report.refs.deleted
+                );
+            }
+        }
+    }
+
+    if include_wikis {
+        let wikis_synced = results
+            .iter()
+            .filter(|(_, _, res)| matches!(res, Ok(report) if report.wiki_synced))
+            .count();
+        println!("📚 {} wikis synced.", wikis_synced);
+    }
+
+    if export_issues {
+        let issues_exported: usize = results
+            .iter()
+            .filter_map(|(_, _, res)| res.as_ref().ok())
+            .map(|report| report.issues_exported)
+            .sum();
+        println!(
+            "🗂️ {} issues/pull requests exported (new or updated).",
+            issues_exported
+        );
+    }
+
+    if backup_releases {
+        let assets_downloaded: usize = results
+            .iter()
+            .filter_map(|(_, _, res)| res.as_ref().ok())
+            .map(|report| report.release_assets_downloaded)
+            .sum();
+        println!("📦 {} release assets downloaded.", assets_downloaded);
+    }
+
+    if args.lfs {
+        let lfs_bytes: u64 = results
+            .iter()
+            .filter_map(|(_, _, res)| res.as_ref().ok())
+            .map(|report| report.lfs_bytes)
+            .sum();
+        println!(
+            "🧱 {} of Git LFS objects transferred.",
+            format_bytes(lfs_bytes)
+        );
+    }
+
+    // History rewritten upstream; the previous tips stay reachable in the backup.
+    for (name, _, result) in &results {
+        if let Ok(report) = result {
+            if !report.overwritten.is_empty() {
+                println!(
+                    "🔁 {}: {} force-pushed ref(s); previous tips kept at {}",
+                    name,
+                    report.overwritten.len(),
+                    report.overwritten.join(", ")
+                );
+            }
+        }
+    }
+
+    // Local commits and changes set aside before a forced reset.
+    for (name, _, result) in &results {
+        if let Ok(report) = result {
+            for preserved in &report.preserved {
+                println!("🛟 {}: {}", name, preserved);
+            }
+        }
+    }
+
+    // Non-fatal problems that did not fail their repository.
+    for (name, _, result) in &results {
+        if let Ok(report) = result {
+            for warning in &report.warnings {
+                eprintln!("[WARNING] {}: {}", name, warning);
+            }
+        }
+    }
+
+    // Private repositories are only returned by authenticated endpoints; report them separately.
+    let private_synced = results
+        .iter()
+        .filter(|(_, is_private, res)| *is_private && res.is_ok())
+        .count();
+    if private_synced > 0 {
+        println!("🔒 {} private repositories synced.", private_synced);
     }
 
     // Error Summary
-    let errors: Vec<_> = results.iter().filter(|(_, res)| res.is_err()).collect();
+    let errors: Vec<_> = results.iter().filter(|(_, _, res)| res.is_err()).collect();
     if !errors.is_empty() {
         println!("\n⚠️ {} operations failed:", errors.len());
-        for (name, result) in errors {
+        for (name, _, result) in errors {
             if let Err(e) = result {
                 eprintln!("[FAILED] {}: {}", name, e);
             }
@@ -235,6 +593,10 @@ async fn main() -> Result<()> {
         return Err(anyhow::anyhow!("Synchronization finished with errors."));
     }
 
+    if prune_refused {
+        return Err(anyhow::anyhow!("Pruning refused by --max-prune."));
+    }
+
     Ok(())
 }
 
@@ -248,6 +610,210 @@ fn destination_path(base_dir: &Path, repo: &github::Repo, root_username: &str) -
     }
 }
 
+// Move clones of renamed or transferred repositories from the path recorded in the manifest to
+// their new destination, along with their wiki, metadata and release backups. The origin URL is
+// updated to the new name by the following sync.
+async fn relocate_renamed_repos(
+    output_dir: &Path,
+    manifest: &manifest::Manifest,
+    repos: &[github::Repo],
+    root_username: &str,
+) -> Result<()> {
+    for repo in repos {
+        let Some(old_path) = renamed_from(output_dir, manifest, repo, root_username).await else {
+            continue;
+        };
+        let new_path = destination_path(output_dir, repo, root_username);
+
+        let siblings = [
+            git::WIKI_SUFFIX,
+            metadata::META_SUFFIX,
+            releases::RELEASES_SUFFIX,
+        ]
+        .map(|suffix| {
+            (
+                files::sibling_path(&old_path, suffix),
+                files::sibling_path(&new_path, suffix),
+            )
+        });
+        for (from, to) in std::iter::once((old_path.clone(), new_path.clone())).chain(siblings) {
+            if !from.exists() || to.exists() {
+                continue;
+            }
+            if let Some(parent) = to.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("Failed to create directory {:?}", parent))?;
+            }
+            tokio::fs::rename(&from, &to)
+                .await
+                .with_context(|| format!("Failed to move {:?} to {:?}", from, to))?;
+        }
+        println!(
+            "🚚 {} was renamed or transferred; moved to {}.",
+            manifest.repos[&repo.id].full_name, repo.full_name
+        );
+    }
+
+    Ok(())
+}
+
+// Previous location of a renamed or transferred repository's clone, if it should be moved:
+// the manifest points elsewhere, a clone is there, and nothing occupies the new path yet.
+async fn renamed_from(
+    output_dir: &Path,
+    manifest: &manifest::Manifest,
+    repo: &github::Repo,
+    root_username: &str,
+) -> Option<PathBuf> {
+    let record = manifest.repos.get(&repo.id)?;
+    let old_path = output_dir.join(&record.path);
+    let new_path = destination_path(output_dir, repo, root_username);
+    if old_path == new_path || new_path.exists() || !is_git_repo(&old_path).await {
+        return None;
+    }
+    Some(old_path)
+}
+
+// Work out what a sync would do, mirroring the decisions made by the real run.
+async fn build_plan(
+    args: &Cli,
+    output_dir: &Path,
+    manifest: &manifest::Manifest,
+    repos: &[github::Repo],
+    desired: &HashSet<PathBuf>,
+) -> Result<plan::Plan> {
+    let mut plan = plan::Plan::default();
+    let username = args.username.as_str();
+
+    for repo in repos {
+        let destination = destination_path(output_dir, repo, username);
+        let moved_from = renamed_from(output_dir, manifest, repo, username).await;
+        let exists = moved_from.is_some() || is_git_repo(&destination).await;
+
+        if !exists {
+            if destination.exists() {
+                plan.reclone.push(destination.clone());
+            } else {
+                plan.clone.push(destination.clone());
+            }
+        } else if !args.full
+            && manifest.is_unchanged(repo)
+            && git::origin_is_current(repo, &destination, args.protocol == Protocol::Ssh).await
+        {
+            plan.skip.push(destination.clone());
+        } else {
+            plan.update.push(destination.clone());
+        }
+
+        if args.include_wikis && repo.has_wiki {
+            let wiki = files::sibling_path(&destination, git::WIKI_SUFFIX);
+            let wiki_exists = match &moved_from {
+                Some(old_path) => {
+                    is_git_repo(&files::sibling_path(old_path, git::WIKI_SUFFIX)).await
+                }
+                None => is_git_repo(&wiki).await,
+            };
+            if wiki_exists {
+                plan.update.push(wiki);
+            } else {
+                plan.clone.push(wiki);
+            }
+        }
+
+        if let Some(from) = moved_from {
+            plan.moves.push(plan::Move {
+                from,
+                to: destination,
+            });
+        }
+    }
+
+    if args.exact_mirror {
+        // Moved clones (and their wikis) are relocated before pruning, so their old paths are
+        // not stale.
+        let moved: HashSet<PathBuf> = plan
+            .moves
+            .iter()
+            .flat_map(|m| {
+                [
+                    m.from.clone(),
+                    files::sibling_path(&m.from, git::WIKI_SUFFIX),
+                ]
+            })
+            .collect();
+        let existing = existing_repo_paths(output_dir).await?.len();
+        let stale: Vec<PathBuf> = stale_repo_paths(output_dir, desired)
+            .await?
+            .into_iter()
+            .filter(|repo_path| !moved.contains(repo_path))
+            .collect();
+        let limit = (!args.yes_prune_all).then_some(args.max_prune);
+        plan.prune_refused = prune_refusal(limit, stale.len(), existing);
+
+        for repo_path in stale {
+            let companions = companion_paths(&repo_path);
+            plan.prune.push(repo_path);
+            plan.prune
+                .extend(companions.into_iter().filter(|path| path.exists()));
+        }
+    }
+
+    Ok(plan)
+}
+
+// Write a Markdown index mapping gist ids (directory names) to their descriptions and files.
+async fn write_gist_index(
+    output_dir: &Path,
+    gists: &[github::Gist],
+    root_username: &str,
+) -> Result<()> {
+    let mut index =
+        String::from("# Gists\n\n| Gist | Description | Files | Visibility |\n|---|---|---|---|\n");
+    for gist in gists {
+        let repo = gist.to_repo(root_username);
+        let relative = destination_path(Path::new("."), &repo, root_username);
+        let mut files: Vec<&str> = gist.files.keys().map(String::as_str).collect();
+        files.sort_unstable();
+        // Keep table cells on one line and avoid breaking the column layout.
+        let description = gist
+            .description
+            .as_deref()
+            .unwrap_or("")
+            .replace(['\n', '\r'], " ")
+            .replace('|', "\\|");
+        index.push_str(&format!(
+            "| [{}]({}) | {} | {} | {} |\n",
+            repo.full_name,
+            relative.display(),
+            description,
+            files.join(", ").replace('|', "\\|"),
+            if gist.public { "public" } else { "secret" }
+        ));
+    }
+
+    let index_path = output_dir.join("INDEX.md");
+    tokio::fs::write(&index_path, index)
+        .await
+        .with_context(|| format!("Failed to write gist index {:?}", index_path))
+}
+
+// Human-readable byte count (e.g. "12.3 MiB").
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
 // Derive the output folder name based on the selected source.
 fn output_dir_name(username: &str, source: &SyncSource) -> String {
     match source {
@@ -256,15 +822,19 @@ fn output_dir_name(username: &str, source: &SyncSource) -> String {
         SyncSource::Following => format!("{}-following", username),
         SyncSource::Followers => format!("{}-followers", username),
         SyncSource::Watching => format!("{}-watching", username),
+        SyncSource::Org => format!("{}-org", username),
+        SyncSource::Mine => format!("{}-mine", username),
+        SyncSource::Gists => format!("{}-gists", username),
     }
 }
 
-// Determine if a path is a git repository by checking for a .git directory.
+// Determine if a path is a git repository by checking for a .git directory (or a bare mirror).
 async fn is_git_repo(path: &Path) -> bool {
     tokio::fs::metadata(path.join(".git"))
         .await
         .map(|m| m.is_dir())
         .unwrap_or(false)
+        || git::is_bare_repository(path)
 }
 
 // Collect existing repository directories under the output folder (direct or nested).
@@ -279,7 +849,7 @@ async fn existing_repo_paths(base_dir: &Path) -> Result<Vec<PathBuf>> {
         .with_context(|| format!("Failed to read directory {:?}", base_dir))?;
     while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
-        if !entry.file_type().await?.is_dir() {
+        if !entry.file_type().await?.is_dir() || entry.file_name() == trash::TRASH_DIR {
             continue;
         }
 
@@ -300,17 +870,81 @@ async fn existing_repo_paths(base_dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(repos)
 }
 
-// Remove repositories not present in the desired set and clean empty owner directories.
-async fn prune_extra_repos(base_dir: &Path, desired: &HashSet<PathBuf>) -> Result<()> {
+// Delete quarantine batches past the retention period.
+async fn purge_trash(output_dir: &Path, retention: Duration) -> Result<()> {
+    let purged = trash::purge_expired(output_dir, retention).await?;
+    if purged > 0 {
+        println!("🗑️ Purged {} expired trash batches.", purged);
+    }
+    Ok(())
+}
+
+// Move a quarantined repository (with its wiki and companion directories) back to its original path.
+async fn restore_from_trash(output_dir: &Path, entry: &Path) -> Result<()> {
+    let restored = trash::restore(output_dir, entry).await?;
+    println!("♻️ Restored {}.", restored.display());
+
+    let [meta, releases] = companion_paths(entry);
+    for companion in [files::sibling_path(entry, git::WIKI_SUFFIX), meta, releases] {
+        if output_dir.join(trash::TRASH_DIR).join(&companion).exists() {
+            let restored = trash::restore(output_dir, &companion).await?;
+            println!("♻️ Restored {}.", restored.display());
+        }
+    }
+    Ok(())
+}
+
+// Existing repositories under the output folder that are not in the desired set.
+async fn stale_repo_paths(base_dir: &Path, desired: &HashSet<PathBuf>) -> Result<Vec<PathBuf>> {
     let existing = existing_repo_paths(base_dir).await?;
-    for repo_path in existing {
-        if !desired.contains(&repo_path) {
-            tokio::fs::remove_dir_all(&repo_path)
-                .await
-                .with_context(|| {
-                    format!("Failed to remove outdated repository at {:?}", repo_path)
-                })?;
+    Ok(existing
+        .into_iter()
+        .filter(|repo_path| !desired.contains(repo_path))
+        .collect())
+}
+
+// Backup data kept next to a repository that goes away with it.
+fn companion_paths(repo_path: &Path) -> [PathBuf; 2] {
+    [
+        files::sibling_path(repo_path, metadata::META_SUFFIX),
+        files::sibling_path(repo_path, releases::RELEASES_SUFFIX),
+    ]
+}
+
+// Quarantine repositories not present in the desired set under `.trash/<stamp>/` and clean
+// empty owner directories. Returns false, pruning nothing, if more repositories would go than
+// `limit` allows.
+async fn prune_extra_repos(
+    base_dir: &Path,
+    desired: &HashSet<PathBuf>,
+    limit: Option<PruneLimit>,
+) -> Result<bool> {
+    let existing = existing_repo_paths(base_dir).await?.len();
+    let stale = stale_repo_paths(base_dir, desired).await?;
+    if let Some(refusal) = prune_refusal(limit, stale.len(), existing) {
+        eprintln!("⚠️ {}", refusal);
+        return Ok(false);
+    }
+
+    let batch = trash::batch_dir(base_dir);
+    for repo_path in stale {
+        let quarantined = trash::quarantine(base_dir, &batch, &repo_path).await?;
+
+        // Exported metadata and release backups go along with their repository.
+        for companion in companion_paths(&repo_path) {
+            if companion.exists() {
+                trash::quarantine(base_dir, &batch, &companion).await?;
+            }
         }
+
+        let entry = quarantined
+            .strip_prefix(base_dir.join(trash::TRASH_DIR))
+            .unwrap_or(&quarantined);
+        println!(
+            "🗑️ Pruned {}; restore with --restore {}",
+            repo_path.display(),
+            entry.display()
+        );
     }
 
     // Clean up empty owner directories left after pruning.
@@ -329,5 +963,107 @@ async fn prune_extra_repos(base_dir: &Path, desired: &HashSet<PathBuf>) -> Resul
         }
     }
 
-    Ok(())
+    Ok(true)
+}
+
+// Explain why pruning `stale` of `existing` repositories exceeds the limit, if it does.
+fn prune_refusal(limit: Option<PruneLimit>, stale: usize, existing: usize) -> Option<String> {
+    let limit = limit?;
+    (!limit.allows(stale, existing)).then(|| {
+        format!(
+            "Refusing to prune {} of {} existing repositories (--max-prune {}); the repository \
+             listing may be incomplete. Re-run with --yes-prune-all to prune anyway.",
+            stale, existing, limit
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An output directory holding `alice/<name>` clones (a `.git` directory is enough) with a
+    // metadata export next to each.
+    fn backup_with(names: &[&str]) -> tempfile::TempDir {
+        let output = tempfile::tempdir().unwrap();
+        for name in names {
+            let repo = output.path().join("alice").join(name);
+            std::fs::create_dir_all(repo.join(".git")).unwrap();
+            std::fs::create_dir_all(files::sibling_path(&repo, metadata::META_SUFFIX)).unwrap();
+        }
+        output
+    }
+
+    fn trash_entries(output: &Path) -> Vec<PathBuf> {
+        let trash = output.join(trash::TRASH_DIR);
+        let mut entries: Vec<PathBuf> = walkdir::WalkDir::new(&trash)
+            .min_depth(3)
+            .max_depth(3)
+            .into_iter()
+            .map(|entry| {
+                let path = entry.unwrap().into_path();
+                // Drop the `<stamp>/` batch directory.
+                path.strip_prefix(&trash)
+                    .unwrap()
+                    .components()
+                    .skip(1)
+                    .collect()
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[tokio::test]
+    async fn prune_refuses_beyond_the_limit_and_touches_nothing() {
+        let output = backup_with(&["a", "b", "c"]);
+        let desired = HashSet::from([output.path().join("alice/a")]);
+
+        for limit in [
+            PruneLimit::Percent(10.0),
+            PruneLimit::Percent(0.0),
+            PruneLimit::Count(1),
+        ] {
+            let pruned = prune_extra_repos(output.path(), &desired, Some(limit))
+                .await
+                .unwrap();
+            assert!(!pruned, "{}", limit);
+        }
+
+        assert!(!output.path().join(trash::TRASH_DIR).exists());
+        for name in ["a", "b", "c", "b.meta", "c.meta"] {
+            assert!(output.path().join("alice").join(name).exists(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_quarantines_stale_repositories_with_their_companions() {
+        let output = backup_with(&["a", "b", "c"]);
+        let desired = HashSet::from([output.path().join("alice/a")]);
+
+        let pruned = prune_extra_repos(output.path(), &desired, Some(PruneLimit::Count(2)))
+            .await
+            .unwrap();
+
+        assert!(pruned);
+        assert_eq!(
+            trash_entries(output.path()),
+            ["alice/b", "alice/b.meta", "alice/c", "alice/c.meta"].map(PathBuf::from)
+        );
+        assert!(output.path().join("alice/a").exists());
+        assert!(output.path().join("alice/a.meta").exists());
+    }
+
+    #[tokio::test]
+    async fn yes_prune_all_lifts_the_limit() {
+        let output = backup_with(&["a", "b", "c"]);
+
+        let pruned = prune_extra_repos(output.path(), &HashSet::new(), None)
+            .await
+            .unwrap();
+
+        assert!(pruned);
+        assert_eq!(trash_entries(output.path()).len(), 6);
+        assert!(!output.path().join("alice").exists());
+    }
 }
@@ -0,0 +1,283 @@
+//
+// manifest.rs
+// GitHub Replicant (Rust)
+//
+// Persists a per-output-directory manifest keyed by GitHub repository id: where each repository lives, its default branch, the last fetched commit and the outcome of recent syncs. Renamed or transferred repositories are found at their previous path through it, and other tooling can read it to judge backup freshness.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use crate::files;
+use crate::git::SyncReport;
+use crate::github::Repo;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Manifest file name inside each output directory.
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// What the previous runs recorded about each repository, keyed by GitHub repository id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub repos: BTreeMap<u64, RepoRecord>,
+}
+
+/// Where a repository was last synced to and how its recent syncs went.
+/// Timestamps are UTC in RFC 3339 form (`2026-10-16T08:30:00Z`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoRecord {
+    pub full_name: String,
+    /// Location relative to the output directory.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    /// Commit at HEAD after the last successful sync.
+    #[serde(default)]
+    pub last_commit: Option<String>,
+    /// Start of the last successful sync.
+    #[serde(default)]
+    pub last_success: Option<String>,
+    /// `pushed_at` GitHub reported for the repository when it was last synced successfully.
+    #[serde(default)]
+    pub pushed_at: Option<String>,
+    /// Start of the last failed sync.
+    #[serde(default)]
+    pub last_failure: Option<String>,
+    /// Error of the most recent sync, cleared once a sync succeeds.
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl Manifest {
+    /// Load the manifest of an output directory. A missing or unreadable manifest is empty.
+    pub async fn load(output_dir: &Path) -> Self {
+        match tokio::fs::read(output_dir.join(MANIFEST_FILE)).await {
+            Ok(contents) => serde_json::from_slice(&contents).unwrap_or_default(),
+            Err(_) => Manifest::default(),
+        }
+    }
+
+    /// Whether nothing was pushed to the repository since its last successful sync, and it was
+    /// not renamed or transferred since. Compares GitHub's own `pushed_at` values rather than
+    /// local clock times.
+    pub fn is_unchanged(&self, repo: &Repo) -> bool {
+        let (Some(pushed_at), Some(record)) = (&repo.pushed_at, self.repos.get(&repo.id)) else {
+            return false;
+        };
+        record.last_error.is_none()
+            && record.full_name == repo.full_name
+            && record.pushed_at.as_ref() == Some(pushed_at)
+    }
+
+    /// Record the outcome of a sync of a repository to `path` (relative to the output directory)
+    /// that began at `started`. Gists have no numeric id and are not tracked.
+    pub fn record_sync(
+        &mut self,
+        repo: &Repo,
+        path: PathBuf,
+        started: String,
+        result: &Result<SyncReport>,
+    ) {
+        if repo.id == 0 {
+            return;
+        }
+        let record = self.repos.entry(repo.id).or_insert_with(|| RepoRecord {
+            full_name: repo.full_name.clone(),
+            path: path.clone(),
+            default_branch: None,
+            last_commit: None,
+            last_success: None,
+            pushed_at: None,
+            last_failure: None,
+            last_error: None,
+        });
+        record.full_name = repo.full_name.clone();
+        record.path = path;
+        record.default_branch = repo.default_branch.clone();
+
+        match result {
+            Ok(report) => {
+                if report.head.is_some() {
+                    record.last_commit = report.head.clone();
+                }
+                record.last_success = Some(started);
+                record.pushed_at = repo.pushed_at.clone();
+                record.last_error = None;
+            }
+            Err(err) => {
+                record.last_failure = Some(started);
+                record.last_error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Write the manifest, replacing the previous one atomically.
+    pub async fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = output_dir.join(MANIFEST_FILE);
+        let contents = serde_json::to_vec_pretty(self).context("Failed to serialize manifest")?;
+        files::write_atomic(&path, contents).await
+    }
+}
+
+/// Current time as an RFC 3339 UTC timestamp with second precision.
+pub fn utc_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Current UTC time as a compact stamp usable in file and ref names (`20261016T083000Z`).
+pub fn utc_stamp() -> String {
+    utc_now()
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect()
+}
+
+/// Parse a stamp made by `utc_stamp` back into seconds since the Unix epoch.
+pub fn parse_utc_stamp(stamp: &str) -> Option<u64> {
+    let (date, time) = stamp.strip_suffix('Z')?.split_once('T')?;
+    if date.len() != 8
+        || time.len() != 6
+        || !(date.bytes().chain(time.bytes())).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let field = |s: &str, range: std::ops::Range<usize>| s[range].parse::<u32>().ok();
+    let (year, month, day) = (field(date, 0..4)?, field(date, 4..6)?, field(date, 6..8)?);
+    let (hour, minute, second) = (field(time, 0..2)?, field(time, 2..4)?, field(time, 4..6)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(i64::from(year), month, day)).ok()?;
+    Some(days * 86_400 + u64::from(hour * 3600 + minute * 60 + second))
+}
+
+// Convert a (year, month, day) date in the proleptic Gregorian calendar to days since
+// 1970-01-01 (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Convert days since 1970-01-01 to a (year, month, day) date in the proleptic Gregorian calendar
+// (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(full_name: &str, pushed_at: Option<&str>) -> Repo {
+        serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "name": full_name.rsplit('/').next().unwrap(),
+            "full_name": full_name,
+            "clone_url": "https://github.com/alice/repo.git",
+            "fork": false,
+            "private": false,
+            "has_wiki": false,
+            "has_issues": false,
+            "pushed_at": pushed_at,
+            "owner": { "login": "alice" },
+        }))
+        .unwrap()
+    }
+
+    fn synced(repo: &Repo, result: Result<SyncReport>) -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.record_sync(repo, PathBuf::from("repo"), utc_now(), &result);
+        manifest
+    }
+
+    #[test]
+    fn unchanged_only_when_github_reports_the_same_push() {
+        let pushed = repo("alice/repo", Some("2026-10-01T10:00:00Z"));
+        let manifest = synced(&pushed, Ok(SyncReport::default()));
+
+        assert!(manifest.is_unchanged(&pushed));
+        // Any other pushed_at means a push happened since.
+        assert!(!manifest.is_unchanged(&repo("alice/repo", Some("2026-10-01T10:00:01Z"))));
+        // Renamed or transferred: the clone's remote must be updated.
+        assert!(!manifest.is_unchanged(&repo("bob/repo", Some("2026-10-01T10:00:00Z"))));
+        assert!(!manifest.is_unchanged(&repo("alice/repo", None)));
+        assert!(!Manifest::default().is_unchanged(&pushed));
+    }
+
+    #[test]
+    fn failed_syncs_are_never_unchanged() {
+        let pushed = repo("alice/repo", Some("2026-10-01T10:00:00Z"));
+        let mut manifest = synced(&pushed, Ok(SyncReport::default()));
+        manifest.record_sync(
+            &pushed,
+            PathBuf::from("repo"),
+            utc_now(),
+            &Err(anyhow::anyhow!("fetch failed")),
+        );
+
+        assert!(!manifest.is_unchanged(&pushed));
+        assert!(!synced(&pushed, Err(anyhow::anyhow!("clone failed"))).is_unchanged(&pushed));
+    }
+
+    #[test]
+    fn utc_stamps_parse_back_to_epoch_seconds() {
+        let cases = [
+            ("19700101T000000Z", Some(0)),
+            ("20000229T120000Z", Some(951_825_600)),
+            ("20261016T083000Z", Some(1_792_139_400)),
+            ("2026-10-16T08:30:00Z", None),
+            ("20261016T083000", None),
+            ("20261316T083000Z", None),
+            ("20261016T246000Z", None),
+            ("2026101aT083000Z", None),
+            ("latest", None),
+        ];
+        for (stamp, expected) in cases {
+            assert_eq!(parse_utc_stamp(stamp), expected, "stamp: {}", stamp);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let parsed = parse_utc_stamp(&utc_stamp()).unwrap();
+        assert!(now.abs_diff(parsed) <= 1, "{} vs {}", parsed, now);
+    }
+}
@@ -0,0 +1,198 @@
+//
+// metadata.rs
+// GitHub Replicant (Rust)
+//
+// Exports the discussion history of a repository (issues, pull requests, comments, review comments, labels and milestones) as JSON files in a sibling `<repo>.meta` directory, updating incrementally with the API's `since` parameter.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use crate::files;
+use crate::github::{self, GitHubClient, Repo};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// File inside `<repo>.meta` remembering where the previous export stopped.
+const STATE_FILE: &str = "export-state.json";
+
+/// Newest `updated_at` seen per incremental collection; used as `since` on the next run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ExportState {
+    /// Whether a full export has finished; later runs only refresh what changed.
+    #[serde(default)]
+    completed: bool,
+    issues_since: Option<String>,
+    /// The pulls endpoint has no `since`; listed pull requests are filtered by `updated_at`.
+    #[serde(default)]
+    pulls_since: Option<String>,
+    comments_since: Option<String>,
+    review_comments_since: Option<String>,
+}
+
+/// Suffix of a repository's metadata export, kept in a sibling `<repo>.meta` directory.
+pub const META_SUFFIX: &str = ".meta";
+
+/// Exports issues, pull requests, comments, review comments, labels and milestones.
+/// Returns how many issues/pull requests were written (new or updated since the last run).
+pub async fn export_issues(client: &GitHubClient, repo: &Repo, repo_path: &Path) -> Result<usize> {
+    let meta_dir = files::sibling_path(repo_path, META_SUFFIX);
+    let full_name = repo.full_name.as_str();
+    let mut state = load_state(&meta_dir).await;
+    let first_run = !state.completed;
+    let mut exported = 0;
+
+    // GitHub's issues feed also lists pull requests, and unlike the pulls endpoint it
+    // supports `since`, so it tells us which pull requests changed.
+    let mut changed_pulls = Vec::new();
+    if repo.has_issues {
+        let issues = github::fetch_repo_items(
+            client,
+            full_name,
+            "issues?state=all",
+            state.issues_since.as_deref(),
+        )
+        .await?;
+        for issue in &issues {
+            let number = item_key(issue, "number")?;
+            if issue.get("pull_request").is_some() {
+                changed_pulls.push(number.clone());
+            }
+            write_json(
+                &meta_dir.join("issues").join(format!("{}.json", number)),
+                issue,
+            )
+            .await?;
+        }
+        exported += issues.len();
+        state.issues_since = newest_update(&issues).or(state.issues_since);
+    }
+
+    // Pull requests: list them on the first run (or when the issues feed is disabled, keeping
+    // only those updated since the last export), afterwards refresh only the ones the issues
+    // feed reported as changed.
+    let pulls = if first_run || !repo.has_issues {
+        let mut pulls =
+            github::fetch_repo_items(client, full_name, "pulls?state=all", None).await?;
+        if let Some(since) = state.pulls_since.as_deref() {
+            pulls.retain(|pull| {
+                pull.get("updated_at")
+                    .and_then(Value::as_str)
+                    .is_some_and(|updated| updated > since)
+            });
+        }
+        pulls
+    } else {
+        let mut pulls = Vec::with_capacity(changed_pulls.len());
+        for number in &changed_pulls {
+            let resource = format!("pulls/{}", number);
+            pulls.push(github::fetch_repo_item(client, full_name, &resource).await?);
+        }
+        pulls
+    };
+    for pull in &pulls {
+        let number = item_key(pull, "number")?;
+        write_json(
+            &meta_dir.join("pulls").join(format!("{}.json", number)),
+            pull,
+        )
+        .await?;
+    }
+    if !repo.has_issues {
+        exported += pulls.len();
+    }
+    state.pulls_since = newest_update(&pulls).or(state.pulls_since);
+
+    if repo.has_issues {
+        let comments = github::fetch_repo_items(
+            client,
+            full_name,
+            "issues/comments",
+            state.comments_since.as_deref(),
+        )
+        .await?;
+        write_items(&meta_dir.join("comments"), &comments).await?;
+        state.comments_since = newest_update(&comments).or(state.comments_since);
+    }
+
+    let review_comments = github::fetch_repo_items(
+        client,
+        full_name,
+        "pulls/comments",
+        state.review_comments_since.as_deref(),
+    )
+    .await?;
+    write_items(&meta_dir.join("review_comments"), &review_comments).await?;
+    state.review_comments_since = newest_update(&review_comments).or(state.review_comments_since);
+
+    // Labels and milestones are small; rewrite them in full each run.
+    let labels = github::fetch_repo_items(client, full_name, "labels", None).await?;
+    write_json(&meta_dir.join("labels.json"), &Value::Array(labels)).await?;
+    let milestones =
+        github::fetch_repo_items(client, full_name, "milestones?state=all", None).await?;
+    write_json(&meta_dir.join("milestones.json"), &Value::Array(milestones)).await?;
+
+    // Only advance the incremental cursors once everything above was written.
+    state.completed = true;
+    save_state(&meta_dir, &state).await?;
+
+    Ok(exported)
+}
+
+// Write each item as `<id>.json` inside the directory.
+async fn write_items(dir: &Path, items: &[Value]) -> Result<()> {
+    for item in items {
+        let id = item_key(item, "id")?;
+        write_json(&dir.join(format!("{}.json", id)), item).await?;
+    }
+    Ok(())
+}
+
+// Pretty-print a JSON value to disk, creating parent directories as needed.
+async fn write_json(path: &Path, value: &Value) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let contents = serde_json::to_vec_pretty(value).context("Failed to serialize JSON")?;
+    tokio::fs::write(path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+// Read a numeric identifier field (`number`, `id`) used to name the exported file.
+fn item_key(item: &Value, field: &str) -> Result<String> {
+    item.get(field)
+        .and_then(Value::as_u64)
+        .map(|n| n.to_string())
+        .ok_or_else(|| anyhow::anyhow!("GitHub API item is missing a numeric '{}' field", field))
+}
+
+// Latest `updated_at` among the items. ISO 8601 UTC timestamps compare correctly as strings.
+fn newest_update(items: &[Value]) -> Option<String> {
+    items
+        .iter()
+        .filter_map(|item| item.get("updated_at").and_then(Value::as_str))
+        .max()
+        .map(str::to_string)
+}
+
+// A missing or unreadable state file means a full export.
+async fn load_state(meta_dir: &Path) -> ExportState {
+    match tokio::fs::read(meta_dir.join(STATE_FILE)).await {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_default(),
+        Err(_) => ExportState::default(),
+    }
+}
+
+async fn save_state(meta_dir: &Path, state: &ExportState) -> Result<()> {
+    let path = meta_dir.join(STATE_FILE);
+    let contents = serde_json::to_vec_pretty(state).context("Failed to serialize export state")?;
+    tokio::fs::create_dir_all(meta_dir)
+        .await
+        .with_context(|| format!("Failed to create directory {:?}", meta_dir))?;
+    tokio::fs::write(&path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", path))
+}
@@ -0,0 +1,75 @@
+//
+// plan.rs
+// GitHub Replicant (Rust)
+//
+// Describes what a sync would do (clones, updates, skips, re-clones, moves of renamed repositories and prunes) for `--dry-run`, printed for humans or written as JSON.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Actions a sync would take, by destination path.
+#[derive(Debug, Default, Serialize)]
+pub struct Plan {
+    /// New clones.
+    pub clone: Vec<PathBuf>,
+    /// Existing clones that would be fetched and updated.
+    pub update: Vec<PathBuf>,
+    /// Existing clones skipped because nothing was pushed since their last sync.
+    pub skip: Vec<PathBuf>,
+    /// Incomplete directories (no git repository) that would be deleted and cloned again.
+    pub reclone: Vec<PathBuf>,
+    /// Clones of renamed or transferred repositories that would be moved.
+    pub moves: Vec<Move>,
+    /// Repositories `--exact-mirror` would move to `.trash`, with their companion directories.
+    pub prune: Vec<PathBuf>,
+    /// Why `--max-prune` would stop the prune, if it would.
+    pub prune_refused: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl Plan {
+    /// Print the plan grouped by action.
+    pub fn print(&self) {
+        println!("📝 Dry run: no repositories were cloned, updated, moved or deleted.");
+        print_paths("Would clone", self.clone.iter());
+        print_paths("Would update", self.update.iter());
+        print_paths("Would skip (unchanged)", self.skip.iter());
+        print_paths("Would delete and re-clone", self.reclone.iter());
+        if !self.moves.is_empty() {
+            println!("Would move ({}):", self.moves.len());
+            for m in &self.moves {
+                println!("  {} -> {}", m.from.display(), m.to.display());
+            }
+        }
+        print_paths("Would prune (move to .trash)", self.prune.iter());
+        if let Some(refusal) = &self.prune_refused {
+            println!("⚠️ {}", refusal);
+        }
+    }
+
+    /// Write the plan as pretty-printed JSON.
+    pub async fn write_json(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(self).context("Failed to serialize plan")?;
+        tokio::fs::write(path, contents)
+            .await
+            .with_context(|| format!("Failed to write plan {:?}", path))
+    }
+}
+
+fn print_paths<'a>(label: &str, paths: impl ExactSizeIterator<Item = &'a PathBuf>) {
+    if paths.len() == 0 {
+        return;
+    }
+    println!("{} ({}):", label, paths.len());
+    for path in paths {
+        println!("  {}", path.display());
+    }
+}
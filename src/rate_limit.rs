@@ -0,0 +1,182 @@
+//
+// rate_limit.rs
+// GitHub Replicant (Rust)
+//
+// Wraps GitHub API requests with rate-limit awareness: pauses until X-RateLimit-Reset or Retry-After with a countdown on the progress bar, backs off exponentially on secondary rate limits, and retries transient 5xx and connection failures.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Maximum number of retries for a single request before giving up.
+const MAX_RETRIES: u32 = 5;
+
+/// Initial delay before retrying a connection failure or 5xx response (doubled per attempt).
+const TRANSIENT_BACKOFF_SECS: u64 = 2;
+
+/// Initial delay for secondary rate limits without a Retry-After header (doubled per attempt).
+/// GitHub asks clients to wait at least one minute in that case.
+const SECONDARY_BACKOFF_SECS: u64 = 60;
+
+/// Tracks the primary rate-limit window so requests pause instead of failing with 403/429.
+#[derive(Default)]
+pub struct RateLimiter {
+    // Epoch seconds at which an exhausted quota resets, if the last response reported zero remaining.
+    exhausted_until: Mutex<Option<u64>>,
+}
+
+impl RateLimiter {
+    /// Sends a GET request with the given extra headers, waiting out rate limits and retrying
+    /// transient failures. Non-retryable responses (including 4xx unrelated to rate limiting
+    /// and 304 Not Modified) are returned as-is so the caller can handle them.
+    pub async fn get(
+        &self,
+        http: &Client,
+        url: &str,
+        headers: &HeaderMap,
+        progress: &ProgressBar,
+    ) -> Result<Response> {
+        let mut attempt: u32 = 0;
+
+        loop {
+            self.wait_for_reset(progress).await;
+
+            let mut response = match http.get(url).headers(headers.clone()).send().await {
+                Ok(response) => response,
+                Err(_) if attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    let delay = backoff(TRANSIENT_BACKOFF_SECS, attempt);
+                    countdown(progress, delay, "Connection error").await;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            self.record(response.headers());
+            let status = response.status();
+
+            if attempt < MAX_RETRIES {
+                if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+                    if let Some(wait) = retry_after(response.headers()) {
+                        // Secondary rate limits usually say exactly how long to wait.
+                        attempt += 1;
+                        countdown(progress, wait, "Rate limited").await;
+                        continue;
+                    }
+                    if remaining(response.headers()) == Some(0) {
+                        // Primary quota exhausted; the reset time was recorded above.
+                        attempt += 1;
+                        continue;
+                    }
+                    // A 403 is only recognizable as a secondary rate limit by its message.
+                    let mut secondary = status == StatusCode::TOO_MANY_REQUESTS;
+                    if !secondary {
+                        let body;
+                        (response, body) = buffer_body(response).await?;
+                        secondary = body.to_lowercase().contains("secondary rate limit");
+                    }
+                    if secondary {
+                        // Secondary rate limit without guidance: back off exponentially.
+                        attempt += 1;
+                        let delay = backoff(SECONDARY_BACKOFF_SECS, attempt);
+                        countdown(progress, delay, "Secondary rate limit").await;
+                        continue;
+                    }
+                } else if status.is_server_error() {
+                    attempt += 1;
+                    let delay = backoff(TRANSIENT_BACKOFF_SECS, attempt);
+                    countdown(progress, delay, &format!("GitHub returned {}", status)).await;
+                    continue;
+                }
+            }
+
+            return Ok(response);
+        }
+    }
+
+    // Remember when an exhausted quota resets so the next request waits for it.
+    fn record(&self, headers: &HeaderMap) {
+        let mut exhausted_until = self.exhausted_until.lock().unwrap();
+        match (remaining(headers), header_u64(headers, "x-ratelimit-reset")) {
+            (Some(0), Some(reset)) => *exhausted_until = Some(reset),
+            (Some(_), _) => *exhausted_until = None,
+            _ => {}
+        }
+    }
+
+    // Sleep until the recorded reset time, if the quota is known to be exhausted.
+    async fn wait_for_reset(&self, progress: &ProgressBar) {
+        let reset = self.exhausted_until.lock().unwrap().take();
+        if let Some(reset) = reset {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            // Add a second of slack so we do not race the reset boundary.
+            let wait = reset.saturating_sub(now) + 1;
+            countdown(
+                progress,
+                Duration::from_secs(wait),
+                "GitHub API rate limit reached",
+            )
+            .await;
+        }
+    }
+}
+
+// Read a response's body as text, returning an equivalent response for the caller alongside it.
+async fn buffer_body(response: Response) -> Result<(Response, String)> {
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = response.bytes().await?;
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+
+    let mut rebuilt = http::Response::new(bytes);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok((Response::from(rebuilt), text))
+}
+
+// Exponential backoff: base * 2^(attempt - 1) seconds.
+fn backoff(base_secs: u64, attempt: u32) -> Duration {
+    Duration::from_secs(base_secs.saturating_mul(1 << attempt.saturating_sub(1).min(16)))
+}
+
+// Show a per-second countdown on the progress bar, then restore the previous message.
+async fn countdown(progress: &ProgressBar, duration: Duration, reason: &str) {
+    let previous = progress.message();
+    let mut seconds_left = duration.as_secs().max(1);
+
+    while seconds_left > 0 {
+        progress.set_message(format!("⏳ {}; resuming in {}s", reason, seconds_left));
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        seconds_left -= 1;
+    }
+
+    progress.set_message(previous);
+}
+
+// Parse `Retry-After` given in seconds (the form GitHub uses).
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+fn remaining(headers: &HeaderMap) -> Option<u64> {
+    header_u64(headers, "x-ratelimit-remaining")
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
@@ -0,0 +1,190 @@
+//
+// releases.rs
+// GitHub Replicant (Rust)
+//
+// Backs up GitHub releases: saves release metadata and notes and streams each release asset to disk under a sibling `<repo>.releases/<tag>/` directory, resuming partial downloads and skipping assets already present with the expected size.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use crate::files;
+use crate::github::{self, GitHubClient, Repo};
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, RANGE};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// Fields of a release needed to lay out its directory.
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+/// A downloadable release asset. `url` is the API URL, which serves the binary when asked
+/// for `application/octet-stream` and also works for private repositories.
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    url: String,
+    size: u64,
+}
+
+/// Suffix of a repository's release backup, kept in a sibling `<repo>.releases` directory.
+pub const RELEASES_SUFFIX: &str = ".releases";
+
+/// Saves every release's metadata and notes and downloads its assets.
+/// Returns how many assets were downloaded (assets already present are skipped).
+pub async fn backup_releases(
+    client: &GitHubClient,
+    repo: &Repo,
+    repo_path: &Path,
+) -> Result<usize> {
+    let releases_dir = files::sibling_path(repo_path, RELEASES_SUFFIX);
+    let items = github::fetch_repo_items(client, &repo.full_name, "releases", None).await?;
+    let mut downloaded = 0;
+
+    for item in items {
+        let release: Release =
+            serde_json::from_value(item.clone()).context("Failed to parse GitHub release JSON")?;
+        let release_dir = releases_dir.join(sanitize_file_name(&release.tag_name));
+        tokio::fs::create_dir_all(&release_dir)
+            .await
+            .with_context(|| format!("Failed to create directory {:?}", release_dir))?;
+
+        // Metadata and notes are small and may be edited on GitHub; rewrite them every run.
+        write_release_info(&release_dir, &release, &item).await?;
+
+        for asset in &release.assets {
+            let destination = release_dir.join(sanitize_file_name(&asset.name));
+            if download_asset(client, asset, &destination)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to download asset {} ({})",
+                        asset.name, release.tag_name
+                    )
+                })?
+            {
+                downloaded += 1;
+            }
+        }
+    }
+
+    Ok(downloaded)
+}
+
+// Write `release.json` (full API metadata) and `NOTES.md` (title and release notes).
+async fn write_release_info(release_dir: &Path, release: &Release, item: &Value) -> Result<()> {
+    let metadata_path = release_dir.join("release.json");
+    let contents = serde_json::to_vec_pretty(item).context("Failed to serialize release JSON")?;
+    tokio::fs::write(&metadata_path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", metadata_path))?;
+
+    let title = release.name.as_deref().unwrap_or(&release.tag_name);
+    let notes = format!("# {}\n\n{}\n", title, release.body.as_deref().unwrap_or(""));
+    let notes_path = release_dir.join("NOTES.md");
+    tokio::fs::write(&notes_path, notes)
+        .await
+        .with_context(|| format!("Failed to write {:?}", notes_path))
+}
+
+// Stream an asset into `<name>.part`, resuming from its current length with a Range request,
+// then move it into place once the size matches. Returns Ok(false) if it was already present.
+async fn download_asset(client: &GitHubClient, asset: &Asset, destination: &Path) -> Result<bool> {
+    if let Ok(metadata) = tokio::fs::metadata(destination).await {
+        if metadata.len() == asset.size {
+            return Ok(false);
+        }
+    }
+
+    let mut part_name = destination.file_name().unwrap_or_default().to_os_string();
+    part_name.push(".part");
+    let part_path = destination.with_file_name(part_name);
+
+    let mut offset = tokio::fs::metadata(&part_path)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    if offset > asset.size {
+        // The asset was replaced by a smaller one; start over.
+        tokio::fs::remove_file(&part_path).await.ok();
+        offset = 0;
+    }
+
+    if offset < asset.size {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/octet-stream"));
+        if offset > 0 {
+            let range = HeaderValue::from_str(&format!("bytes={}-", offset))
+                .context("Invalid Range header")?;
+            headers.insert(RANGE, range);
+        }
+
+        let mut response = client.get(&asset.url, &headers).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("HTTP {} while downloading", status));
+        }
+
+        // Servers that ignore Range answer 200 with the whole file; overwrite in that case.
+        let mut file = if offset > 0 && status == StatusCode::PARTIAL_CONTENT {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part_path)
+                .await
+        } else {
+            tokio::fs::File::create(&part_path).await
+        }
+        .with_context(|| format!("Failed to open {:?}", part_path))?;
+
+        while let Some(chunk) = response.chunk().await.context("Download interrupted")? {
+            file.write_all(&chunk)
+                .await
+                .with_context(|| format!("Failed to write {:?}", part_path))?;
+        }
+        file.flush().await?;
+    }
+
+    // Keep a short partial file for the next run to resume; discard an oversized one.
+    let written = tokio::fs::metadata(&part_path).await?.len();
+    if written != asset.size {
+        if written > asset.size {
+            tokio::fs::remove_file(&part_path).await.ok();
+        }
+        return Err(anyhow::anyhow!(
+            "size mismatch: expected {} bytes, got {}",
+            asset.size,
+            written
+        ));
+    }
+
+    tokio::fs::rename(&part_path, destination)
+        .await
+        .with_context(|| format!("Failed to move {:?} into place", part_path))?;
+    Ok(true)
+}
+
+// Tags and asset names may contain path separators; keep each one a single path component.
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    match sanitized.as_str() {
+        "" | "." | ".." => format!("_{}", sanitized),
+        _ => sanitized,
+    }
+}
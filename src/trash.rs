@@ -0,0 +1,195 @@
+//
+// trash.rs
+// GitHub Replicant (Rust)
+//
+// Quarantines pruned repositories in a timestamped `.trash/<stamp>/` directory inside the output folder instead of deleting them, purges batches older than the retention period, and moves quarantined entries back to their original path on request.
+//
+// Thales Matheus Mendonça Santos - October 2026
+
+use crate::manifest;
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Quarantine directory inside each output folder.
+pub const TRASH_DIR: &str = ".trash";
+
+/// Directory for the entries quarantined by this run: `.trash/<YYYYMMDDTHHMMSSZ>`.
+pub fn batch_dir(output_dir: &Path) -> PathBuf {
+    output_dir.join(TRASH_DIR).join(manifest::utc_stamp())
+}
+
+/// Move `path` (inside `output_dir`) to the same relative location inside `batch`.
+/// Returns the quarantined path.
+pub async fn quarantine(output_dir: &Path, batch: &Path, path: &Path) -> Result<PathBuf> {
+    let relative = path
+        .strip_prefix(output_dir)
+        .with_context(|| format!("{:?} is outside {:?}", path, output_dir))?;
+    let target = batch.join(relative);
+    move_dir(path, &target).await?;
+    Ok(target)
+}
+
+/// Move a quarantined entry, given relative to `.trash` (`<stamp>/<path>`), back to its original
+/// location. Fails if something already exists there.
+pub async fn restore(output_dir: &Path, entry: &Path) -> Result<PathBuf> {
+    // The entry must be `<stamp>/<relative path>` without any `..` escaping the output folder.
+    let mut components = entry.components();
+    let valid = matches!(components.next(), Some(Component::Normal(_)))
+        && components.clone().next().is_some()
+        && components
+            .clone()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Expected a trash entry of the form <stamp>/<path>, got {:?}",
+            entry
+        ));
+    }
+
+    let source = output_dir.join(TRASH_DIR).join(entry);
+    let original = output_dir.join(components.as_path());
+    if !source.exists() {
+        return Err(anyhow::anyhow!("Nothing quarantined at {:?}", source));
+    }
+    if original.exists() {
+        return Err(anyhow::anyhow!(
+            "Cannot restore {:?}: {:?} already exists",
+            source,
+            original
+        ));
+    }
+    move_dir(&source, &original).await?;
+    Ok(original)
+}
+
+/// Delete quarantine batches older than `retention`, judged by the stamp in their name. Entries
+/// whose name is not a stamp are left alone. Returns how many batches were removed.
+pub async fn purge_expired(output_dir: &Path, retention: Duration) -> Result<usize> {
+    let trash = output_dir.join(TRASH_DIR);
+    if !trash.exists() {
+        return Ok(0);
+    }
+
+    let mut purged = 0;
+    let mut entries = tokio::fs::read_dir(&trash)
+        .await
+        .with_context(|| format!("Failed to read directory {:?}", trash))?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    while let Some(entry) = entries.next_entry().await? {
+        // The batch name records when its entries were quarantined; unlike the directory's
+        // modification time, restoring part of a batch or copying it does not change that.
+        let Some(stamped) = entry
+            .file_name()
+            .to_str()
+            .and_then(manifest::parse_utc_stamp)
+        else {
+            continue;
+        };
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        if Duration::from_secs(now.saturating_sub(stamped)) >= retention {
+            tokio::fs::remove_dir_all(entry.path())
+                .await
+                .with_context(|| format!("Failed to purge {:?}", entry.path()))?;
+            purged += 1;
+        }
+    }
+
+    Ok(purged)
+}
+
+// Rename a directory, creating the target's parent directories first.
+async fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    tokio::fs::rename(from, to)
+        .await
+        .with_context(|| format!("Failed to move {:?} to {:?}", from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn quarantined_repositories_restore_to_their_path() {
+        let output = tempfile::tempdir().unwrap();
+        let repo = output.path().join("alice/repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::write(repo.join("README"), "kept").unwrap();
+        let batch = output.path().join(TRASH_DIR).join("20260101T000000Z");
+
+        let quarantined = quarantine(output.path(), &batch, &repo).await.unwrap();
+        assert_eq!(quarantined, batch.join("alice/repo"));
+        assert!(!repo.exists());
+
+        let restored = restore(output.path(), Path::new("20260101T000000Z/alice/repo"))
+            .await
+            .unwrap();
+        assert_eq!(restored, repo);
+        assert_eq!(
+            std::fs::read_to_string(repo.join("README")).unwrap(),
+            "kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn restore_rejects_entries_outside_a_batch() {
+        let output = tempfile::tempdir().unwrap();
+        let batch = output.path().join(TRASH_DIR).join("20260101T000000Z");
+        std::fs::create_dir_all(batch.join("alice/repo")).unwrap();
+        std::fs::create_dir_all(output.path().join("alice/taken")).unwrap();
+        std::fs::create_dir_all(batch.join("alice/taken")).unwrap();
+
+        let rejected = [
+            "20260101T000000Z",
+            "../alice/repo",
+            "20260101T000000Z/../../alice/repo",
+            "/20260101T000000Z/alice/repo",
+            "./20260101T000000Z/alice/repo",
+            "20260101T000000Z/alice/missing",
+            // Never overwrite what is already at the original path.
+            "20260101T000000Z/alice/taken",
+        ];
+        for entry in rejected {
+            assert!(
+                restore(output.path(), Path::new(entry)).await.is_err(),
+                "{}",
+                entry
+            );
+        }
+        assert!(batch.join("alice/repo").exists());
+        assert!(batch.join("alice/taken").exists());
+    }
+
+    #[tokio::test]
+    async fn purge_judges_batches_by_their_stamp() {
+        let output = tempfile::tempdir().unwrap();
+        let trash = output.path().join(TRASH_DIR);
+        let old = trash.join("20200101T000000Z");
+        let recent = trash.join(manifest::utc_stamp());
+        let unrelated = trash.join("notes");
+        for dir in [&old, &recent, &unrelated] {
+            std::fs::create_dir_all(dir.join("alice/repo")).unwrap();
+        }
+        // Touching the old batch (as a partial restore does) must not restart its retention.
+        std::fs::create_dir(old.join("alice/other")).unwrap();
+
+        let purged = purge_expired(output.path(), Duration::from_secs(86_400))
+            .await
+            .unwrap();
+
+        assert_eq!(purged, 1);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(unrelated.exists());
+    }
+}